@@ -0,0 +1,849 @@
+use std::ops::Range;
+
+/// A completion or symbol label, kept free of Zed types so it can be tested
+/// natively. `spans` are concatenated to form the displayed text, and
+/// `filter_range` indexes into that displayed text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    pub code: String,
+    pub spans: Vec<LabelSpan>,
+    pub filter_range: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LabelSpan {
+    /// A range of `code`, highlighted by parsing `code` as Odin.
+    Code(Range<usize>),
+    /// Text shown as-is with an optional highlight name.
+    Literal {
+        text: String,
+        highlight: Option<&'static str>,
+    },
+}
+
+/// Highlight for the collection of an import, matching how `highlights.scm`
+/// colors package names.
+pub const COLLECTION_HIGHLIGHT: &str = "module";
+
+/// Highlights for directive (`#partial`) and attribute (`@(require_results)`)
+/// keyword completions, matching the captures `highlights.scm` uses for them.
+pub const DIRECTIVE_HIGHLIGHT: &str = "keyword.directive";
+pub const ATTRIBUTE_HIGHLIGHT: &str = "attribute";
+
+/// Highlight for polymorphic parameters such as `$T`, so they stand out from
+/// the ordinary parameters the Odin grammar highlights around them.
+pub const POLYMORPHIC_HIGHLIGHT: &str = "type.parameter";
+
+/// Labels longer than this many characters are shortened with `…` so huge
+/// struct, procedure and generic type details stay readable in the menu.
+pub const MAX_LABEL_CHARS: usize = 80;
+
+const ELLIPSIS: &str = "…";
+
+impl Label {
+    /// Shows all of `code`, filtering on its first `filter_len` bytes.
+    /// Polymorphic `$` parameters get their own highlight, and code past
+    /// [`MAX_LABEL_CHARS`] is elided after the filtered prefix.
+    pub fn plain(code: String, filter_len: usize) -> Self {
+        let code = shorten(code, filter_len, MAX_LABEL_CHARS);
+        let spans = polymorphic_spans(&code);
+        Label {
+            code,
+            spans,
+            filter_range: 0..filter_len,
+        }
+    }
+
+    /// Shows only `span_range` of `code`, filtering on the first `filter_len`
+    /// bytes of what is shown.
+    pub fn with_span(code: String, span_range: Range<usize>, filter_len: usize) -> Self {
+        Label {
+            code,
+            spans: vec![LabelSpan::Code(span_range)],
+            filter_range: 0..filter_len,
+        }
+    }
+}
+
+/// Byte ranges of every matched `()`, `[]` and `{}` pair in `code` at or after
+/// `from`, as `(open, close)` indices. Brackets inside string and rune
+/// literals are ignored.
+fn bracket_pairs(code: &str, from: usize) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    let mut stack = Vec::new();
+    let mut quote = None;
+    let mut chars = code.char_indices().skip_while(|(i, _)| *i < from);
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some('`'), _) => {}
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(_), _) => {}
+            (None, '"' | '`' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => stack.push((i, c)),
+            (None, ')' | ']' | '}') => {
+                let expected = match c {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if let Some(position) = stack.iter().rposition(|(_, open)| *open == expected) {
+                    let (open, _) = stack[position];
+                    stack.truncate(position);
+                    pairs.push((open, i));
+                }
+            }
+            (None, _) => {}
+        }
+    }
+    pairs
+}
+
+/// Splits bracket contents on the commas at their top level.
+fn top_level_items(content: &str) -> Vec<&str> {
+    let nested: Vec<(usize, usize)> = bracket_pairs(content, 0);
+    let mut items = Vec::new();
+    let mut start = 0;
+    for (i, c) in content.char_indices() {
+        if c == ',' && !nested.iter().any(|(open, close)| *open < i && i < *close) {
+            items.push(content[start..i].trim());
+            start = i + 1;
+        }
+    }
+    items.push(content[start..].trim());
+    items
+}
+
+/// Shortens the bracket group with the longest contents: its middle items
+/// first (`(a: int, …, z: int)`), then all of it (`(…)`). Returns `None` when
+/// nothing after `keep` can be shortened any further.
+fn elide_longest_group(code: &str, keep: usize) -> Option<String> {
+    let (open, close) = bracket_pairs(code, keep)
+        .into_iter()
+        .max_by_key(|(open, close)| close - open)?;
+    let content = &code[open + 1..close];
+    let content_chars = content.chars().count();
+    let items = top_level_items(content);
+    let replacement = match items.as_slice() {
+        [first, .., last] if items.len() > 2 => Some(format!("{first}, {ELLIPSIS}, {last}")),
+        _ => None,
+    }
+    .filter(|elided| elided.chars().count() < content_chars)
+    .unwrap_or_else(|| ELLIPSIS.to_string());
+    if replacement.chars().count() >= content_chars {
+        return None;
+    }
+    Some(format!("{}{replacement}{}", &code[..=open], &code[close..]))
+}
+
+/// The brackets, and any string or rune literal, still open at the end of
+/// `code`, innermost last.
+fn unclosed_brackets(code: &str) -> Vec<char> {
+    let mut open = Vec::new();
+    let mut quote = None;
+    for c in code.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '`' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => open.push(c),
+            (None, ')' | ']' | '}') => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    open.extend(quote);
+    open
+}
+
+/// Shortens `code` to `max_chars` characters without touching its first
+/// `keep` bytes (the name being filtered on). Parameter lists lose
+/// their middle items first, then whole bracket groups collapse to `(…)`;
+/// only if that is still too long is the tail cut off, after which any
+/// bracket or literal left open is closed again (past the limit) so the
+/// label stays balanced.
+pub fn shorten(mut code: String, keep: usize, max_chars: usize) -> String {
+    let keep = keep.min(code.len());
+    while code.chars().count() > max_chars {
+        match elide_longest_group(&code, keep) {
+            Some(shorter) => code = shorter,
+            None => break,
+        }
+    }
+    if code.chars().count() <= max_chars {
+        return code;
+    }
+
+    let cut = code
+        .char_indices()
+        .map(|(i, _)| i)
+        .nth(max_chars.saturating_sub(1))
+        .unwrap_or(code.len());
+    let cut = (cut.max(keep)..=code.len())
+        .find(|i| code.is_char_boundary(*i))
+        .unwrap_or(code.len());
+    let unclosed = unclosed_brackets(&code[..cut]);
+    code.truncate(cut);
+    code.push_str(ELLIPSIS);
+    for open in unclosed.into_iter().rev() {
+        code.push(match open {
+            '(' => ')',
+            '[' => ']',
+            '{' => '}',
+            quote => quote,
+        });
+    }
+    code
+}
+
+/// Splits `code` into code ranges and literal `$name` spans. String and rune
+/// literals are skipped, so a `$` inside them is left alone.
+fn polymorphic_spans(code: &str) -> Vec<LabelSpan> {
+    let mut spans = Vec::new();
+    let mut code_start = 0;
+    let mut quote = None;
+    let mut chars = code.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some('`'), '`') | (Some('"'), '"') | (Some('\''), '\'') => quote = None,
+            (Some('`'), _) => {}
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(_), _) => {}
+            (None, '"' | '`' | '\'') => quote = Some(c),
+            (None, '$') => {
+                let mut end = i + 1;
+                while let Some(&(j, next)) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    end = j + next.len_utf8();
+                    chars.next();
+                }
+                if end == i + 1 {
+                    continue;
+                }
+                if code_start < i {
+                    spans.push(LabelSpan::Code(code_start..i));
+                }
+                spans.push(LabelSpan::Literal {
+                    text: code[i..end].to_string(),
+                    highlight: Some(POLYMORPHIC_HIGHLIGHT),
+                });
+                code_start = end;
+            }
+            (None, _) => {}
+        }
+    }
+    if code_start < code.len() || spans.is_empty() {
+        spans.push(LabelSpan::Code(code_start..code.len()));
+    }
+    spans
+}
+
+/// The balanced parameter list that directly follows `keyword` in `detail`,
+/// e.g. `($N: int, $T: typeid)` for `struct($N: int, $T: typeid) {...}`.
+pub fn parameter_list<'a>(detail: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = detail.trim_start().strip_prefix(keyword)?.trim_start();
+    if !rest.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&rest[..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `name :: struct($N: int, $T: typeid)` for a parametric struct or union,
+/// `name :: struct` otherwise. `keyword` is `struct` or `union`.
+pub fn record_label(name: &str, keyword: &str, detail: Option<&str>) -> Label {
+    let code = match detail.and_then(|detail| parameter_list(strip_declared_name(detail), keyword))
+    {
+        Some(params) => format!("{name} :: {keyword}{params}"),
+        None => format!("{name} :: {keyword}"),
+    };
+    Label::plain(code, name.len())
+}
+
+/// The shape of an Odin type expression, as found in OLS detail strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeExpr {
+    Struct,
+    Union,
+    Enum,
+    BitField,
+    BitSet,
+    Matrix,
+    /// `[Enum]T` or `#sparse[Enum]T`. An array sized by a named constant,
+    /// `[N]T`, looks the same and lands here too; both display alike.
+    EnumeratedArray,
+    /// `[4]T`.
+    FixedArray,
+    /// `[]T`.
+    Slice,
+    /// `[dynamic]T`.
+    DynamicArray,
+    /// `#soa[]T`, `#soa[4]T` or `#soa[dynamic]T`.
+    Soa,
+    /// `#simd[4]f32`.
+    Simd,
+    Map,
+    /// `^T`.
+    Pointer,
+    /// `[^]T`.
+    MultiPointer,
+    Distinct,
+    Proc,
+}
+
+fn starts_with_keyword(text: &str, keyword: &str) -> bool {
+    text.strip_prefix(keyword)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+/// Whether `expr` is a procedure type or group, possibly behind directives:
+/// `proc(x: int)`, `proc "c" ()`, `#force_inline proc()`, `proc{a, b}`.
+/// Type names that merely start with `proc`, like `process`, are not.
+fn is_proc_type(expr: &str) -> bool {
+    let mut rest = expr.trim_start();
+    while rest.starts_with('#') {
+        let directive_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[directive_end..].trim_start();
+    }
+    starts_with_keyword(rest, "proc")
+}
+
+/// Classifies an Odin type expression such as `matrix[4, 4]f32`,
+/// `[Direction]Vec2` or `#soa[]Entity`. Plain type names return `None`.
+pub fn classify_type_expr(expr: &str) -> Option<TypeExpr> {
+    let expr = expr.trim();
+    let keywords = [
+        ("struct", TypeExpr::Struct),
+        ("union", TypeExpr::Union),
+        ("enum", TypeExpr::Enum),
+        ("bit_field", TypeExpr::BitField),
+        ("bit_set", TypeExpr::BitSet),
+        ("matrix", TypeExpr::Matrix),
+        ("map", TypeExpr::Map),
+        ("distinct", TypeExpr::Distinct),
+        ("#soa", TypeExpr::Soa),
+        ("#simd", TypeExpr::Simd),
+        ("#sparse", TypeExpr::EnumeratedArray),
+    ];
+    if let Some((_, kind)) = keywords
+        .iter()
+        .find(|(keyword, _)| starts_with_keyword(expr, keyword))
+    {
+        return Some(*kind);
+    }
+    if expr.starts_with('^') {
+        return Some(TypeExpr::Pointer);
+    }
+    if is_proc_type(expr) {
+        return Some(TypeExpr::Proc);
+    }
+    let (index, _) = expr.strip_prefix('[')?.split_once(']')?;
+    let index = index.trim();
+    Some(match index {
+        "" => TypeExpr::Slice,
+        "^" => TypeExpr::MultiPointer,
+        "dynamic" => TypeExpr::DynamicArray,
+        _ if index.starts_with(|c: char| c.is_alphabetic() || c == '_') => {
+            TypeExpr::EnumeratedArray
+        }
+        _ => TypeExpr::FixedArray,
+    })
+}
+
+/// The part of a declaration worth showing in a one-line label: the header
+/// before the `{` body for records, enums and bit fields (`enum u8`,
+/// `bit_field u32`), or the whole expression for everything else.
+fn declaration_head(kind: TypeExpr, declaration: &str) -> &str {
+    match kind {
+        TypeExpr::Struct | TypeExpr::Union | TypeExpr::Enum | TypeExpr::BitField => declaration
+            .split_once('{')
+            .map_or(declaration, |(head, _)| head)
+            .trim(),
+        _ => declaration,
+    }
+}
+
+/// The label for a type declaration, shared by completions and symbols:
+/// `name :: bit_field u32`, `name :: matrix[4, 4]f32`, `name :: #soa[]T`,
+/// `name :: enum u8` and so on. Details that are not a type expression fall
+/// back to `name :: <fallback_keyword>`.
+pub fn type_label(name: &str, detail: Option<&str>, fallback_keyword: &str) -> Label {
+    let declaration = detail
+        .map(strip_declared_name)
+        .filter(|declaration| !declaration.is_empty());
+    let Some(declaration) = declaration else {
+        return Label::plain(format!("{name} :: {fallback_keyword}"), name.len());
+    };
+    if fallback_keyword == "enum" && is_integer_type(declaration) {
+        return Label::plain(format!("{name} :: enum {declaration}"), name.len());
+    }
+    match classify_type_expr(declaration) {
+        Some(TypeExpr::Struct) => record_label(name, "struct", Some(declaration)),
+        Some(TypeExpr::Union) => record_label(name, "union", Some(declaration)),
+        Some(kind) => Label::plain(
+            format!("{name} :: {}", declaration_head(kind, declaration)),
+            name.len(),
+        ),
+        None => Label::plain(format!("{name} :: {fallback_keyword}"), name.len()),
+    }
+}
+
+/// Whether an `Enum`-kind declaration is really a union. OLS reports unions
+/// with the `Enum` kind in both completions and symbols, so the detail is the
+/// only signal: a union type expression, or the `union` keyword anywhere in it
+/// (e.g. `Value :: #no_nil union {int, f32}` from older releases).
+pub fn is_union_detail(detail: &str) -> bool {
+    let declaration = strip_declared_name(detail);
+    match classify_type_expr(declaration) {
+        Some(TypeExpr::Union) => true,
+        Some(_) => false,
+        None => declaration
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|word| word == "union"),
+    }
+}
+
+/// The label for an `Enum`-kind completion or symbol, which may be an enum
+/// or a union depending on `detail`.
+pub fn enum_or_union_label(name: &str, detail: Option<&str>) -> Label {
+    match detail {
+        Some(detail) if is_union_detail(detail) => {
+            let declaration = strip_declared_name(detail);
+            let declaration = declaration
+                .find("union")
+                .map_or(declaration, |start| &declaration[start..]);
+            record_label(name, "union", Some(declaration))
+        }
+        detail => type_label(name, detail, "enum"),
+    }
+}
+
+/// `$T: typeid` for a polymorphic type parameter, filtering on `T`.
+pub fn type_parameter_label(name: &str, detail: Option<&str>) -> Label {
+    let name = name.strip_prefix('$').unwrap_or(name);
+    let code = match detail.map(str::trim).filter(|detail| !detail.is_empty()) {
+        Some(detail) => format!("${name}: {detail}"),
+        None => format!("${name}"),
+    };
+    let mut label = Label::plain(code, 1 + name.len());
+    label.filter_range = 1..1 + name.len();
+    label
+}
+
+pub fn is_integer_type(type_str: &str) -> bool {
+    matches!(
+        type_str,
+        // Basic signed integers
+        "int" | "i8" | "i16" | "i32" | "i64" | "i128" |
+        // Basic unsigned integers
+        "uint" | "u8" | "u16" | "u32" | "u64" | "u128" | "uintptr" |
+        // Integer aliases
+        "byte" | "rune" |
+        // Little-endian integers
+        "i16le" | "i32le" | "i64le" | "i128le" |
+        "u16le" | "u32le" | "u64le" | "u128le" |
+        // Big-endian integers
+        "i16be" | "i32be" | "i64be" | "i128be" |
+        "u16be" | "u32be" | "u64be" | "u128be"
+    )
+}
+
+/// Drops a leading `name ::` from an OLS detail string, which some OLS
+/// versions repeat in front of the declaration.
+fn strip_declared_name(detail: &str) -> &str {
+    match detail.split_once("::") {
+        Some((name, rest))
+            if !name.trim().is_empty()
+                && name
+                    .trim()
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '.') =>
+        {
+            rest.trim_start()
+        }
+        _ => detail.trim(),
+    }
+}
+
+/// The procedure type of a completion, e.g. `proc "c" (x: int) -> f32` or
+/// `#force_inline proc(v: Vec3) -> f32`. Prefers `detail`, and otherwise
+/// rebuilds it from the LSP label details (`(x: int)` and `f32`).
+pub fn proc_signature(
+    detail: Option<&str>,
+    label_detail: Option<&str>,
+    label_description: Option<&str>,
+) -> String {
+    let detail = detail.map(strip_declared_name);
+    if let Some(signature) = detail.filter(|signature| is_proc_type(signature)) {
+        return signature.to_string();
+    }
+    // A bare parameter list, e.g. `(x: process) -> int`.
+    if let Some(params) = detail.filter(|detail| detail.starts_with('(')) {
+        return format!("proc{params}");
+    }
+
+    let Some(params) = label_detail
+        .map(str::trim)
+        .filter(|params| params.starts_with('('))
+    else {
+        return "proc".to_string();
+    };
+    match label_description
+        .map(str::trim)
+        .map(|returns| returns.trim_start_matches("->").trim_start())
+        .filter(|returns| !returns.is_empty())
+    {
+        Some(returns) => format!("proc{params} -> {returns}"),
+        None => format!("proc{params}"),
+    }
+}
+
+/// Proc groups longer than this are collapsed to their first members and a
+/// count of the rest, e.g. `proc{a, b, +3}`.
+pub const MAX_PROC_GROUP_MEMBERS: usize = 3;
+
+/// The members of a proc group (overloaded procedure) signature such as
+/// `proc{a, b, c}`, or `None` if `signature` is a single procedure.
+pub fn proc_group_members(signature: &str) -> Option<Vec<&str>> {
+    let members = signature
+        .strip_prefix("proc")?
+        .trim_start()
+        .strip_prefix('{')?
+        .strip_suffix('}')?;
+    Some(
+        members
+            .split(',')
+            .map(str::trim)
+            .filter(|member| !member.is_empty())
+            .collect(),
+    )
+}
+
+pub fn proc_group_signature(members: &[&str]) -> String {
+    if members.len() <= MAX_PROC_GROUP_MEMBERS {
+        return format!("proc{{{}}}", members.join(", "));
+    }
+    let shown = MAX_PROC_GROUP_MEMBERS - 1;
+    format!(
+        "proc{{{}, +{}}}",
+        members[..shown].join(", "),
+        members.len() - shown
+    )
+}
+
+/// `name :: proc(x: int, y: f32) -> (res: T, ok: bool)` for a procedure, or
+/// `name :: proc{a, b, c}` for a proc group, filtering on `name`.
+pub fn proc_label(
+    name: &str,
+    detail: Option<&str>,
+    label_detail: Option<&str>,
+    label_description: Option<&str>,
+) -> Label {
+    let mut signature = proc_signature(detail, label_detail, label_description);
+    if let Some(members) = proc_group_members(&signature) {
+        signature = proc_group_signature(&members);
+    }
+    Label::plain(format!("{name} :: {signature}"), name.len())
+}
+
+/// Finds a `collection:path` import in OLS detail text such as
+/// `core:strings`, `import "vendor:raylib"` or `strings (core:strings)`.
+pub fn collection_import(text: &str) -> Option<(&str, &str)> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(':').find_map(|(colon, _)| {
+        let before = &text[..colon];
+        let start = before.trim_end_matches(is_name).len();
+        let collection = &before[start..];
+        let after = &text[colon + 1..];
+        let path = &after[..after
+            .find(|c: char| c == '"' || c == ')' || c.is_whitespace())
+            .unwrap_or(after.len())];
+        let valid = !collection.is_empty()
+            && !collection.starts_with(|c: char| c.is_ascii_digit())
+            && !path.is_empty()
+            && !path.starts_with(['/', '\\']);
+        valid.then_some((collection, path))
+    })
+}
+
+/// `import "core:strings"` with the collection highlighted, filtering on the
+/// package name. Falls back to `package name` when none of `sources` carries
+/// a collection.
+pub fn import_label(name: &str, sources: &[Option<&str>]) -> Label {
+    let Some((collection, path)) = sources.iter().flatten().find_map(|s| collection_import(s))
+    else {
+        let code = format!("package {name}");
+        return Label::with_span(code, 8..8 + name.len(), name.len());
+    };
+
+    let code = format!("import \"{collection}:{path}\"");
+    let collection_start = "import \"".len();
+    let collection_end = collection_start + collection.len();
+    let package = path.rsplit('/').next().unwrap_or(path);
+    let package_start = code.len() - 1 - package.len();
+    let filter_range = if package == name || name.is_empty() {
+        package_start..package_start + package.len()
+    } else {
+        match code[collection_end..].find(name) {
+            Some(offset) => collection_end + offset..collection_end + offset + name.len(),
+            None => package_start..package_start + package.len(),
+        }
+    };
+    Label {
+        spans: vec![
+            LabelSpan::Code(0..collection_start),
+            LabelSpan::Literal {
+                text: collection.to_string(),
+                highlight: Some(COLLECTION_HIGHLIGHT),
+            },
+            LabelSpan::Code(collection_end..code.len()),
+        ],
+        filter_range,
+        code,
+    }
+}
+
+/// Splits a qualified symbol name such as `Vec3.x` or `renderer.draw` into
+/// its container and member.
+pub fn split_container(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once('.') {
+        Some((container, member)) if !container.is_empty() && !member.is_empty() => {
+            (Some(container), member)
+        }
+        _ => (None, name),
+    }
+}
+
+/// Splits the span that covers display offset `at`, so a new span can be
+/// inserted there, and returns the index to insert it at.
+fn split_spans_at(spans: &mut Vec<LabelSpan>, at: usize) -> usize {
+    let mut offset = 0;
+    for index in 0..spans.len() {
+        let len = match &spans[index] {
+            LabelSpan::Code(range) => range.len(),
+            LabelSpan::Literal { text, .. } => text.len(),
+        };
+        if at <= offset {
+            return index;
+        }
+        if at < offset + len {
+            let split = at - offset;
+            let tail = match &mut spans[index] {
+                LabelSpan::Code(range) => {
+                    let tail = range.start + split..range.end;
+                    range.end = range.start + split;
+                    LabelSpan::Code(tail)
+                }
+                LabelSpan::Literal { text, highlight } => LabelSpan::Literal {
+                    text: text.split_off(split),
+                    highlight: *highlight,
+                },
+            };
+            spans.insert(index + 1, tail);
+            return index + 1;
+        }
+        offset += len;
+    }
+    spans.len()
+}
+
+/// Qualifies the name in `label` with `container.`, highlighting the
+/// container with `highlight`. The container goes right before the filtered
+/// name, so `using x: Base` becomes `using Vec3.x: Base`, and the filter
+/// range grows to cover it, so the qualified name can be searched for.
+pub fn with_container(mut label: Label, container: Option<&str>, highlight: &'static str) -> Label {
+    let Some(container) = container else {
+        return label;
+    };
+    let prefix_len = container.len() + 1;
+    let index = split_spans_at(&mut label.spans, label.filter_range.start);
+    label.spans.splice(
+        index..index,
+        [
+            LabelSpan::Literal {
+                text: container.to_string(),
+                highlight: Some(highlight),
+            },
+            LabelSpan::Literal {
+                text: ".".to_string(),
+                highlight: None,
+            },
+        ],
+    );
+    label.filter_range.end += prefix_len;
+    label
+}
+
+/// Rewrites a type expression with the spacing the Odin grammar expects,
+/// recursing through pointer, array and map prefixes: `^ Node` becomes
+/// `^Node` and `map[ string ] [ dynamic ]int` becomes `map[string][dynamic]int`.
+/// Anything else is returned trimmed.
+pub fn format_type_expr(expr: &str) -> String {
+    let expr = expr.trim();
+    let matching_bracket = |open: usize| {
+        bracket_pairs(expr, open)
+            .into_iter()
+            .find_map(|(o, close)| (o == open).then_some(close))
+    };
+    match classify_type_expr(expr) {
+        Some(TypeExpr::Pointer) => format!("^{}", format_type_expr(&expr[1..])),
+        Some(
+            TypeExpr::Slice
+            | TypeExpr::MultiPointer
+            | TypeExpr::DynamicArray
+            | TypeExpr::FixedArray
+            | TypeExpr::EnumeratedArray,
+        ) if expr.starts_with('[') => match matching_bracket(0) {
+            Some(close) => format!(
+                "[{}]{}",
+                expr[1..close].trim(),
+                format_type_expr(&expr[close + 1..])
+            ),
+            None => expr.to_string(),
+        },
+        Some(TypeExpr::Map) => {
+            let open = expr.len() - expr["map".len()..].trim_start().len();
+            match (expr[open..].starts_with('['), matching_bracket(open)) {
+                (true, Some(close)) => format!(
+                    "map[{}]{}",
+                    format_type_expr(&expr[open + 1..close]),
+                    format_type_expr(&expr[close + 1..])
+                ),
+                _ => expr.to_string(),
+            }
+        }
+        _ => expr.to_string(),
+    }
+}
+
+/// `name: T` when the type is known, otherwise just `name`. Details that
+/// repeat the name (`pos: Vec3`) are reduced to the type, and fields pulled
+/// in with `using` render as `using name: T` while still filtering on
+/// `name`.
+pub fn variable_label(name: &str, type_name: Option<&str>) -> Label {
+    let detail = type_name.map(str::trim).unwrap_or_default();
+    let (using, detail) = match detail.strip_prefix("using") {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            (true, rest.trim_start())
+        }
+        _ => (false, detail),
+    };
+    let detail = match detail.strip_prefix(name).map(str::trim_start) {
+        Some(rest) if rest.starts_with(':') && !rest.starts_with("::") => rest[1..].trim(),
+        _ => detail,
+    };
+    let prefix = if using { "using " } else { "" };
+    let code = match format_type_expr(detail) {
+        type_name if type_name.is_empty() => format!("{prefix}{name}"),
+        type_name => format!("{prefix}{name}: {type_name}"),
+    };
+    let mut label = Label::plain(code, prefix.len() + name.len());
+    label.filter_range = prefix.len()..prefix.len() + name.len();
+    label
+}
+
+/// Just `name`, highlighted as a package.
+pub fn package_label(name: &str) -> Label {
+    Label::with_span(format!("package {name}"), 8..8 + name.len(), name.len())
+}
+
+/// Just `name`, highlighted as a type.
+pub fn type_name_label(name: &str) -> Label {
+    Label::with_span(format!("{name} :: struct"), 0..name.len(), name.len())
+}
+
+/// A keyword completion, with directives such as `#partial` and attributes
+/// such as `@(require_results)` highlighted the way the buffer shows them.
+pub fn keyword_label(keyword: &str) -> Label {
+    let highlight = if keyword.starts_with('#') {
+        DIRECTIVE_HIGHLIGHT
+    } else if keyword.starts_with('@') {
+        ATTRIBUTE_HIGHLIGHT
+    } else {
+        "keyword"
+    };
+    Label {
+        code: keyword.to_string(),
+        spans: vec![LabelSpan::Literal {
+            text: keyword.to_string(),
+            highlight: Some(highlight),
+        }],
+        filter_range: 0..keyword.len(),
+    }
+}
+
+/// Renders an LSP snippet as the text it expands to: `${1:value}` becomes
+/// `value`, bare tab stops (`$1`, `${2}`, `$0`) disappear, and runs of
+/// whitespace collapse so multi-line snippets fit on one line.
+pub fn expand_snippet(snippet: &str) -> String {
+    let mut expanded = String::with_capacity(snippet.len());
+    let mut open_placeholders = 0;
+    let mut chars = snippet.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => expanded.extend(chars.next()),
+            '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                while chars.peek().is_some_and(char::is_ascii_digit) {
+                    chars.next();
+                }
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                while chars.peek().is_some_and(char::is_ascii_digit) {
+                    chars.next();
+                }
+                if chars.peek() == Some(&':') {
+                    chars.next();
+                }
+                open_placeholders += 1;
+            }
+            '}' if open_placeholders > 0 => open_placeholders -= 1,
+            _ => expanded.push(c),
+        }
+    }
+    expanded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A snippet completion such as `fmt.println`, previewing its expansion:
+/// `fmt.println(args)`, or `name  expansion` when the expansion does not
+/// start with the name. Filters on `name` either way.
+pub fn snippet_label(name: &str, snippet: Option<&str>) -> Label {
+    let expansion = snippet
+        .map(expand_snippet)
+        .filter(|expansion| !expansion.is_empty() && expansion != name);
+    let Some(expansion) = expansion else {
+        return Label::plain(name.to_string(), name.len());
+    };
+    if expansion.starts_with(name) {
+        return Label::plain(expansion, name.len());
+    }
+    let mut label = Label::plain(expansion, 0);
+    label.spans.splice(
+        0..0,
+        [LabelSpan::Literal {
+            text: format!("{name}  "),
+            highlight: None,
+        }],
+    );
+    label.filter_range = 0..name.len();
+    label
+}
@@ -8,6 +8,87 @@ pub const NIGHTLY_TAG: &str = "nightly";
 /// that replaces the bundled `odin.py` formatter for every debug session.
 pub const LLDB_SCRIPT_SETTING_KEY: &str = "lldb_script";
 
+/// Key under `lsp.ols.settings` that opts into deriving `collections` from the
+/// project layout.
+pub const AUTO_COLLECTIONS_SETTING_KEY: &str = "auto_collections";
+
+/// Key under `lsp.ols.settings` that moves debug builds out of the default
+/// directory in the extension's work dir. Relative paths resolve against the
+/// task's cwd and must already exist.
+pub const DEBUG_OUTPUT_DIR_SETTING_KEY: &str = "debug_output_dir";
+
+/// Directory under the extension's work dir that holds debug builds, one
+/// subdirectory per project.
+pub const DEFAULT_DEBUG_OUTPUT_DIR: &str = "odin-debug";
+
+/// Key under `lsp.ols.settings` that rewrites `-o:` optimization flags to
+/// `-o:none` in debug builds, so locals are not optimized away.
+pub const DEBUG_DOWNGRADE_OPTIMIZATION_SETTING_KEY: &str = "debug_downgrade_optimization";
+
+/// Key under `lsp.ols.settings` listing scripts that wrap the Odin compiler
+/// and take its arguments, e.g. `["./build.sh"]`, so their tasks get debug
+/// variants too.
+pub const DEBUG_WRAPPER_COMMANDS_SETTING_KEY: &str = "debug_wrapper_commands";
+
+/// Keys under `lsp.ols.settings` naming the executable, and its arguments,
+/// that debug sessions of `-build-mode:dll` builds launch to load the library.
+pub const DEBUG_HOST_PROGRAM_SETTING_KEY: &str = "debug_host_program";
+pub const DEBUG_HOST_ARGS_SETTING_KEY: &str = "debug_host_args";
+
+/// Keys under `lsp.ols.settings` for attach sessions started without a pid:
+/// the executable name or path to attach to, and whether to wait for its next
+/// launch instead of attaching to a running process.
+pub const DEBUG_ATTACH_PROGRAM_SETTING_KEY: &str = "debug_attach_program";
+pub const DEBUG_ATTACH_WAIT_FOR_SETTING_KEY: &str = "debug_attach_wait_for";
+
+/// Key under `lsp.ols.settings` that turns off stopping on Odin panics,
+/// failed assertions and bounds-check failures. On by default.
+pub const DEBUG_BREAK_ON_PANIC_SETTING_KEY: &str = "debug_break_on_panic";
+
+/// Procedures Odin calls once a panic, a failed `assert` or a failed bounds
+/// check or type assertion has already happened, unlike `assert` or
+/// `bounds_check_error` themselves. `panic` hands over to the assertion
+/// failure proc, so only the latter is listed to stop once per panic.
+/// `core:testing` installs its own failure proc, which covers tests.
+pub const PANIC_BREAKPOINT_SYMBOLS: &[&str] = &[
+    "runtime.default_assertion_failure_proc",
+    "runtime.default_assertion_contextless_failure_proc",
+    "testing.test_assertion_failure_proc",
+    "runtime.bounds_trap",
+    "runtime.type_assertion_trap",
+];
+
+/// Breakpoint name LLDB reports for [`PANIC_BREAKPOINT_SYMBOLS`] hits.
+pub const PANIC_BREAKPOINT_NAME: &str = "odin-panic";
+
+/// Key under `lsp.ols.settings` that keeps the test runner's memory tracking
+/// on in test debug builds, for hunting leaks. Off by default.
+pub const DEBUG_TEST_TRACK_MEMORY_SETTING_KEY: &str = "debug_test_track_memory";
+
+/// Sanitizers Odin supports through `-sanitize:`, with the environment
+/// variable that configures each one's runtime and the options a debug
+/// session wants: abort on the first report so the debugger stops right
+/// there. LeakSanitizer cannot run under a debugger, so ASan leak checks
+/// are off.
+pub const SANITIZERS: &[(&str, &str, &str)] = &[
+    ("address", "ASAN_OPTIONS", "abort_on_error=1:detect_leaks=0"),
+    ("memory", "MSAN_OPTIONS", "abort_on_error=1"),
+    ("thread", "TSAN_OPTIONS", "halt_on_error=1:abort_on_error=1"),
+];
+
+/// Build modes that produce a library, which needs a host program to debug.
+pub const LIBRARY_BUILD_MODES: &[&str] = &["dll", "shared", "dynamic"];
+
+/// File name prefix of every debug build, so pruning never touches anything
+/// else that lives in a shared output directory.
+pub const DEBUG_OUTPUT_PREFIX: &str = "debug_build";
+
+/// Collections the Odin compiler always knows about.
+pub const BUILTIN_COLLECTIONS: &[&str] = &["base", "core", "vendor", "shared"];
+
+/// Top-level directories that are conventionally their own collection.
+pub const CONVENTIONAL_COLLECTIONS: &[&str] = &["src", "libs"];
+
 pub fn format_check_record(checked_at_secs: u64, version: &str) -> String {
     format!("{checked_at_secs} {version}")
 }
@@ -47,6 +128,30 @@ pub fn lldb_prerun_command(script: &str) -> String {
     )
 }
 
+/// Puts `commands` at the front of a debug config's `preRunCommands`, after
+/// which any commands the config already had still run. Commands already
+/// present are not added twice, so converting a config again is harmless.
+pub fn inject_pre_run_commands(
+    config: &mut serde_json::Map<String, serde_json::Value>,
+    commands: Vec<String>,
+) {
+    let existing: Vec<serde_json::Value> = match config.remove("preRunCommands") {
+        Some(serde_json::Value::Array(existing)) => existing,
+        Some(serde_json::Value::Null) | None => Vec::new(),
+        Some(command) => vec![command],
+    };
+    let mut merged: Vec<serde_json::Value> = commands
+        .into_iter()
+        .map(serde_json::Value::String)
+        .filter(|command| !existing.contains(command))
+        .collect();
+    merged.extend(existing);
+    config.insert(
+        "preRunCommands".to_string(),
+        serde_json::Value::Array(merged),
+    );
+}
+
 pub fn ols_version_dir(version: &str) -> String {
     format!("ols-{version}")
 }
@@ -80,28 +185,371 @@ pub fn strip_extension_settings(settings: &mut serde_json::Value) {
     if let Some(settings) = settings.as_object_mut() {
         settings.remove("release_tag");
         settings.remove(LLDB_SCRIPT_SETTING_KEY);
+        settings.remove(AUTO_COLLECTIONS_SETTING_KEY);
+        settings.remove(DEBUG_OUTPUT_DIR_SETTING_KEY);
+        settings.remove(DEBUG_DOWNGRADE_OPTIMIZATION_SETTING_KEY);
+        settings.remove(DEBUG_WRAPPER_COMMANDS_SETTING_KEY);
+        settings.remove(DEBUG_HOST_PROGRAM_SETTING_KEY);
+        settings.remove(DEBUG_HOST_ARGS_SETTING_KEY);
+        settings.remove(DEBUG_ATTACH_PROGRAM_SETTING_KEY);
+        settings.remove(DEBUG_ATTACH_WAIT_FOR_SETTING_KEY);
+        settings.remove(DEBUG_BREAK_ON_PANIC_SETTING_KEY);
+        settings.remove(DEBUG_TEST_TRACK_MEMORY_SETTING_KEY);
     }
 }
 
-pub fn merged_initialization_options(user: Option<serde_json::Value>) -> serde_json::Value {
-    let mut options = serde_json::json!({
-        "enable_hover": true,
-        "enable_document_symbols": true,
-        "enable_snippets": true,
-        "enable_references": true,
-        "enable_inlay_hints_params": true,
-        "enable_inlay_hints_default_params": true,
-    });
+pub fn auto_collections_from_settings(settings: Option<&serde_json::Value>) -> bool {
+    settings
+        .and_then(|settings| settings.get(AUTO_COLLECTIONS_SETTING_KEY))
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Key of the per-OS override block inside `lsp.ols.settings` and
+/// `initialization_options`.
+pub const PLATFORM_OVERRIDES_KEY: &str = "platform";
+
+/// Key inside a `settings` platform block that overrides `lsp.ols.binary`.
+pub const PLATFORM_BINARY_KEY: &str = "binary";
+
+/// Merges `overrides` into `base`: objects are merged key by key, anything
+/// else (including arrays) replaces the base value.
+pub fn deep_merge(base: &mut serde_json::Value, overrides: serde_json::Value) {
+    match (base, overrides) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Removes the `platform` block from `value` and merges the entry for `os`
+/// (`"mac"`, `"linux"` or `"windows"`) over the rest of it.
+pub fn apply_platform_overrides(value: &mut serde_json::Value, os: &str) {
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    let Some(mut platforms) = object.remove(PLATFORM_OVERRIDES_KEY) else {
+        return;
+    };
+    if let Some(overrides) = platforms.get_mut(os).map(serde_json::Value::take) {
+        if overrides.is_object() {
+            deep_merge(value, overrides);
+        }
+    }
+}
+
+/// Options enabled by default for every OLS release the extension supports.
+/// OLS ships with every feature disabled, so these are the conservative set
+/// that even the oldest pinnable releases understand.
+pub const BASE_DEFAULT_OPTIONS: &[&str] = &[
+    "enable_hover",
+    "enable_document_symbols",
+    "enable_snippets",
+    "enable_references",
+    "enable_inlay_hints_params",
+    "enable_inlay_hints_default_params",
+];
+
+/// Options enabled by default only when the resolved OLS release is at least
+/// the given tag. Older releases either reject unknown keys or silently ignore
+/// them, so they are never sent to a binary that predates them.
+pub const VERSIONED_DEFAULT_OPTIONS: &[(&str, &[&str])] = &[
+    ("dev-2025-04", &["enable_document_highlights"]),
+    ("dev-2025-07", &["enable_inlay_hints_implicit_return"]),
+];
+
+/// Extracts the release from a downloaded binary path (`ols-<release>/<exe>`).
+/// Custom and `PATH` binaries have no known release.
+pub fn installed_release<'a>(binary_path: &'a str, separator: &str) -> Option<&'a str> {
+    let (dir, _) = binary_path.split_once(separator)?;
+    dir.strip_prefix("ols-")
+        .filter(|release| !release.is_empty())
+}
+
+fn release_date(release: &str) -> Option<Vec<u32>> {
+    release
+        .strip_prefix("dev-")?
+        .split('-')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Whether `release` is `min_release` or newer. Nightly builds are always the
+/// newest; unrecognised or unknown releases only get the base defaults.
+pub fn release_at_least(release: Option<&str>, min_release: &str) -> bool {
+    match release {
+        Some(NIGHTLY_TAG) => true,
+        Some(release) => match (release_date(release), release_date(min_release)) {
+            (Some(release), Some(min_release)) => release >= min_release,
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// The version-gated defaults the resolved release qualifies for, for logging.
+pub fn versioned_defaults_for(release: Option<&str>) -> Vec<&'static str> {
+    VERSIONED_DEFAULT_OPTIONS
+        .iter()
+        .filter(|(min_release, _)| release_at_least(release, min_release))
+        .flat_map(|(_, keys)| keys.iter().copied())
+        .collect()
+}
+
+/// OLS option keys that were renamed, as `(deprecated, current)` pairs. A
+/// key that was split maps to every key that replaced it. Shared settings
+/// files written against older releases keep using the old names, which
+/// newer releases silently ignore.
+pub const RENAMED_OPTIONS: &[(&str, &[&str])] = &[
+    (
+        "enable_inlay_hints",
+        &[
+            "enable_inlay_hints_params",
+            "enable_inlay_hints_default_params",
+            "enable_inlay_hints_implicit_return",
+        ],
+    ),
+    (
+        "enable_inlay_hints_default_parameters",
+        &["enable_inlay_hints_default_params"],
+    ),
+    (
+        "enable_checker_only_on_save",
+        &["enable_checker_only_saved"],
+    ),
+    ("checker_arguments", &["checker_args"]),
+];
+
+/// Rewrites deprecated keys in the user's options to their current names and
+/// returns one notice per migrated key. A value the user also set under a
+/// current name always wins over the deprecated one, and version-gated keys
+/// that `release` predates are not added.
+pub fn migrate_deprecated_options(
+    options: &mut serde_json::Map<String, serde_json::Value>,
+    release: Option<&str>,
+) -> Vec<String> {
+    let supported = |key: &str| {
+        VERSIONED_DEFAULT_OPTIONS
+            .iter()
+            .filter(|(_, keys)| keys.contains(&key))
+            .all(|(min_release, _)| release_at_least(release, min_release))
+    };
+    let quoted = |keys: &[&str]| {
+        keys.iter()
+            .map(|key| format!("`{key}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut notices = Vec::new();
+    for (deprecated, current) in RENAMED_OPTIONS {
+        let Some(value) = options.remove(*deprecated) else {
+            continue;
+        };
+        let (kept, applied): (Vec<&str>, Vec<&str>) = current
+            .iter()
+            .filter(|key| supported(key))
+            .partition(|key| options.contains_key(**key));
+        for key in &applied {
+            options.insert(key.to_string(), value.clone());
+        }
+        notices.push(match (applied.is_empty(), kept.is_empty()) {
+            (true, _) => format!(
+                "OLS option `{deprecated}` is deprecated and was ignored because {} is also set",
+                quoted(&kept)
+            ),
+            (false, true) => format!(
+                "OLS option `{deprecated}` is deprecated, using it as {}",
+                quoted(&applied)
+            ),
+            (false, false) => format!(
+                "OLS option `{deprecated}` is deprecated, using it as {} ({} is also set and wins)",
+                quoted(&applied),
+                quoted(&kept)
+            ),
+        });
+    }
+    notices
+}
+
+/// Layers the user's `initialization_options` over the defaults for `release`,
+/// returning the merged options and a notice for every deprecated key that
+/// was migrated.
+pub fn merged_initialization_options(
+    user: Option<serde_json::Value>,
+    release: Option<&str>,
+) -> (serde_json::Value, Vec<String>) {
+    let mut defaults = serde_json::Map::new();
+    for key in BASE_DEFAULT_OPTIONS
+        .iter()
+        .copied()
+        .chain(versioned_defaults_for(release))
+    {
+        defaults.insert(key.to_string(), serde_json::Value::Bool(true));
+    }
     match user {
-        Some(serde_json::Value::Object(user)) => {
-            let defaults = options.as_object_mut().unwrap();
+        Some(serde_json::Value::Object(mut user)) => {
+            let notices = migrate_deprecated_options(&mut user, release);
             for (key, value) in user {
                 defaults.insert(key, value);
             }
-            options
+            (serde_json::Value::Object(defaults), notices)
+        }
+        Some(other) => (other, Vec::new()),
+        None => (serde_json::Value::Object(defaults), Vec::new()),
+    }
+}
+
+/// Returns the `(collection, path)` of every `import "collection:path"` in
+/// `source`, with or without an import alias.
+pub fn collection_imports(source: &str) -> Vec<(String, String)> {
+    source
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("import"))
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .filter_map(|rest| {
+            let quoted = rest.split('"').nth(1)?;
+            let (collection, path) = quoted.split_once(':')?;
+            (!collection.is_empty()).then(|| (collection.to_string(), path.to_string()))
+        })
+        .collect()
+}
+
+/// Names of the collections already configured in `options` (or an `ols.json`).
+pub fn configured_collections(options: &serde_json::Value) -> Vec<String> {
+    options
+        .get("collections")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|collection| collection.get("name")?.as_str())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Top-level directories of the submodules listed in a `.gitmodules` file,
+/// where vendored libraries usually live.
+pub fn gitmodule_top_dirs(gitmodules: &str) -> Vec<String> {
+    let mut dirs: Vec<String> = gitmodules
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("path"))
+        .filter_map(|rest| rest.trim_start().strip_prefix('='))
+        .filter_map(|path| path.trim().split('/').next())
+        .filter(|dir| !dir.is_empty())
+        .map(str::to_string)
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Top-level directories confirmed to exist by reading a file inside them.
+/// Extensions cannot list directories, so this probes the files a directory
+/// usually holds: `main.odin` or `<dir>.odin` for the
+/// [`CONVENTIONAL_COLLECTIONS`], and `<collection>/<path>/<package>.odin`
+/// for every imported package.
+pub fn probe_top_dirs(imports: &[(String, String)], is_file: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut dirs: Vec<String> = Vec::new();
+    let mut confirm = |dir: &str, candidates: &[String]| {
+        if !dirs.iter().any(|known| known == dir) && candidates.iter().any(|file| is_file(file)) {
+            dirs.push(dir.to_string());
+        }
+    };
+    for dir in CONVENTIONAL_COLLECTIONS {
+        let mut candidates = vec![format!("{dir}/main.odin"), format!("{dir}/{dir}.odin")];
+        // Vendored library directories hold packages rather than code of
+        // their own, so the packages imported from them are the best probe.
+        candidates.extend(
+            imports
+                .iter()
+                .filter(|(collection, _)| collection == dir)
+                .map(|(_, path)| package_file(dir, path)),
+        );
+        confirm(dir, &candidates);
+    }
+    for (collection, path) in imports {
+        confirm(collection, &[package_file(collection, path)]);
+    }
+    dirs
+}
+
+/// The file a package directory is conventionally named after, e.g.
+/// `libs/jolt/jolt.odin` for `import "libs:jolt"`.
+fn package_file(collection: &str, path: &str) -> String {
+    let package = path.rsplit('/').next().unwrap_or(path);
+    format!("{collection}/{path}/{package}.odin")
+}
+
+/// Collections derived from the project layout: every conventional top-level
+/// directory found in the worktree and every imported collection that matches
+/// a top-level directory. Collections that are built in or already configured
+/// are never proposed again.
+#[derive(Debug, Default, PartialEq)]
+pub struct AutoCollections {
+    /// Collection names that resolve to a top-level directory of the worktree.
+    pub found: Vec<String>,
+    /// Imported collection names with no matching top-level directory.
+    pub unresolved: Vec<String>,
+}
+
+pub fn auto_collections(
+    imports: &[(String, String)],
+    top_dirs: &[String],
+    configured: &[String],
+) -> AutoCollections {
+    let is_known = |name: &str| {
+        BUILTIN_COLLECTIONS.contains(&name) || configured.iter().any(|known| known == name)
+    };
+    let mut result = AutoCollections::default();
+    for dir in top_dirs {
+        if CONVENTIONAL_COLLECTIONS.contains(&dir.as_str()) && !is_known(dir) {
+            result.found.push(dir.clone());
+        }
+    }
+    for (collection, _) in imports {
+        if is_known(collection)
+            || result.found.contains(collection)
+            || result.unresolved.contains(collection)
+        {
+            continue;
+        }
+        if top_dirs.contains(collection) {
+            result.found.push(collection.clone());
+        } else {
+            result.unresolved.push(collection.clone());
+        }
+    }
+    result
+}
+
+/// Appends a `{ name, path }` entry under `root` to `options["collections"]`
+/// for each found collection. Non-object options are left untouched.
+pub fn inject_collections(
+    options: &mut serde_json::Value,
+    found: &[String],
+    root: &str,
+    separator: &str,
+) {
+    let Some(options) = options.as_object_mut() else {
+        return;
+    };
+    let collections = options
+        .entry("collections")
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    if let Some(collections) = collections.as_array_mut() {
+        for name in found {
+            collections.push(serde_json::json!({
+                "name": name,
+                "path": format!("{root}{separator}{name}"),
+            }));
         }
-        Some(other) => other,
-        None => options,
     }
 }
 
@@ -109,6 +557,7 @@ pub fn debug_output_name(resolved_label: &str, exe_suffix: &str) -> String {
     let target = resolved_label
         .strip_prefix("run: ")
         .or_else(|| resolved_label.strip_prefix("test: "))
+        .or_else(|| resolved_label.strip_prefix("build: "))
         .unwrap_or(resolved_label);
 
     let mut sanitized = String::with_capacity(target.len());
@@ -126,10 +575,423 @@ pub fn debug_output_name(resolved_label: &str, exe_suffix: &str) -> String {
     if trimmed.is_empty() {
         format!("debug_build{exe_suffix}")
     } else {
-        format!("debug_build-{trimmed}{exe_suffix}")
+        format!("{DEBUG_OUTPUT_PREFIX}-{trimmed}{exe_suffix}")
+    }
+}
+
+/// Debug session settings read from `lsp.ols.settings`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DebugSettings {
+    pub output_dir: Option<String>,
+    pub downgrade_optimization: bool,
+    pub wrapper_commands: Vec<String>,
+    pub host_program: Option<String>,
+    pub host_args: Vec<String>,
+    pub attach_program: Option<String>,
+    pub attach_wait_for: bool,
+    pub break_on_panic: bool,
+    pub test_track_memory: bool,
+}
+
+impl Default for DebugSettings {
+    fn default() -> Self {
+        DebugSettings {
+            output_dir: None,
+            downgrade_optimization: false,
+            wrapper_commands: Vec::new(),
+            host_program: None,
+            host_args: Vec::new(),
+            attach_program: None,
+            attach_wait_for: false,
+            break_on_panic: true,
+            test_track_memory: false,
+        }
+    }
+}
+
+pub fn debug_settings_from(settings: Option<&serde_json::Value>) -> DebugSettings {
+    let string_setting = |key: &str| {
+        settings?
+            .get(key)?
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let bool_setting = |key: &str| {
+        settings
+            .and_then(|settings| settings.get(key))
+            .and_then(serde_json::Value::as_bool)
+    };
+    let list_setting = |key: &str| -> Vec<String> {
+        settings
+            .and_then(|settings| settings.get(key))
+            .and_then(serde_json::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(serde_json::Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    DebugSettings {
+        output_dir: string_setting(DEBUG_OUTPUT_DIR_SETTING_KEY),
+        downgrade_optimization: bool_setting(DEBUG_DOWNGRADE_OPTIMIZATION_SETTING_KEY)
+            .unwrap_or(false),
+        wrapper_commands: list_setting(DEBUG_WRAPPER_COMMANDS_SETTING_KEY),
+        host_program: string_setting(DEBUG_HOST_PROGRAM_SETTING_KEY),
+        host_args: list_setting(DEBUG_HOST_ARGS_SETTING_KEY),
+        attach_program: string_setting(DEBUG_ATTACH_PROGRAM_SETTING_KEY),
+        attach_wait_for: bool_setting(DEBUG_ATTACH_WAIT_FOR_SETTING_KEY).unwrap_or(false),
+        break_on_panic: bool_setting(DEBUG_BREAK_ON_PANIC_SETTING_KEY).unwrap_or(true),
+        test_track_memory: bool_setting(DEBUG_TEST_TRACK_MEMORY_SETTING_KEY).unwrap_or(false),
+    }
+}
+
+/// Pins the test runner defines of a test debug build: one thread so
+/// breakpoints in one test are not interleaved with others, and no clipboard
+/// or fancy output. Any of these the task sets itself are replaced; every
+/// other define, including `ODIN_TEST_NAMES`, is kept. Memory tracking
+/// follows `track_memory` unless the task sets `ODIN_TEST_TRACK_MEMORY`
+/// itself, in which case the task's value wins.
+pub fn apply_test_debug_defines(args: &mut Vec<String>, track_memory: bool) {
+    let define_key = |arg: &str| -> Option<String> {
+        arg.strip_prefix("-define:")
+            .and_then(|define| define.split_once('='))
+            .map(|(key, _)| key.trim().to_string())
+    };
+    let mut defines = vec![
+        ("ODIN_TEST_THREADS", "1"),
+        ("ODIN_TEST_CLIPBOARD", "false"),
+        ("ODIN_TEST_FANCY", "false"),
+    ];
+    args.retain(|arg| {
+        !define_key(arg).is_some_and(|key| defines.iter().any(|(pinned, _)| *pinned == key))
+    });
+    if !args
+        .iter()
+        .any(|arg| define_key(arg).as_deref() == Some("ODIN_TEST_TRACK_MEMORY"))
+    {
+        defines.push((
+            "ODIN_TEST_TRACK_MEMORY",
+            if track_memory { "true" } else { "false" },
+        ));
+    }
+    args.extend(
+        defines
+            .iter()
+            .map(|(key, value)| format!("-define:{key}={value}")),
+    );
+}
+
+/// LLDB command that stops on every [`PANIC_BREAKPOINT_SYMBOLS`] procedure
+/// with a single breakpoint named [`PANIC_BREAKPOINT_NAME`]. It stays pending
+/// in programs that never link the procedure.
+pub fn panic_breakpoint_command() -> String {
+    let names: Vec<String> = PANIC_BREAKPOINT_SYMBOLS
+        .iter()
+        .map(|symbol| format!("-n {symbol}"))
+        .collect();
+    format!(
+        "breakpoint set -N {PANIC_BREAKPOINT_NAME} {}",
+        names.join(" ")
+    )
+}
+
+/// The process an attach session connects to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttachTarget {
+    Pid(u32),
+    /// A process found by executable name or path. With `wait_for`, the
+    /// debugger waits for the next process with that name to start.
+    Program {
+        program: String,
+        wait_for: bool,
+    },
+}
+
+/// Picks the attach target: an explicit pid, then the configured
+/// `debug_attach_program`, then the last debug build, whose next launch is
+/// waited for since it is usually not running yet.
+pub fn attach_target(
+    pid: Option<u32>,
+    settings: &DebugSettings,
+    last_debug_build: Option<&str>,
+) -> Option<AttachTarget> {
+    if let Some(pid) = pid {
+        return Some(AttachTarget::Pid(pid));
+    }
+    if let Some(program) = &settings.attach_program {
+        return Some(AttachTarget::Program {
+            program: program.clone(),
+            wait_for: settings.attach_wait_for,
+        });
+    }
+    last_debug_build.map(|program| AttachTarget::Program {
+        program: program.to_string(),
+        wait_for: true,
+    })
+}
+
+/// The CodeLLDB attach configuration for `target`.
+pub fn attach_config(target: &AttachTarget) -> serde_json::Map<String, serde_json::Value> {
+    let mut config = serde_json::Map::new();
+    config.insert("request".to_string(), serde_json::json!("attach"));
+    match target {
+        AttachTarget::Pid(pid) => {
+            config.insert("pid".to_string(), serde_json::json!(pid));
+        }
+        AttachTarget::Program { program, wait_for } => {
+            config.insert("program".to_string(), serde_json::json!(program));
+            if *wait_for {
+                config.insert("waitFor".to_string(), serde_json::json!(true));
+            }
+        }
+    }
+    config
+}
+
+/// The known sanitizers a task's `-sanitize:` flags enable, in order and
+/// without repeats.
+pub fn requested_sanitizers(args: &[String]) -> Vec<&'static str> {
+    let mut sanitizers = Vec::new();
+    for requested in args.iter().filter_map(|arg| arg.strip_prefix("-sanitize:")) {
+        if let Some((name, _, _)) = SANITIZERS.iter().find(|(name, _, _)| *name == requested) {
+            if !sanitizers.contains(name) {
+                sanitizers.push(*name);
+            }
+        }
+    }
+    sanitizers
+}
+
+/// Tells sanitized scenarios apart from the plain one: `label` gets a
+/// `(address sanitizer)` style suffix unless it already ends with exactly
+/// that suffix, as the bundled sanitizer tasks do. The debug build's output
+/// name derives from this label, so sanitized and plain builds never
+/// overwrite each other.
+pub fn sanitizer_label(label: &str, sanitizers: &[&str]) -> String {
+    if sanitizers.is_empty() {
+        return label.to_string();
+    }
+    let suffix = format!("({} sanitizer)", sanitizers.join(" + "));
+    if label.ends_with(&suffix) {
+        label.to_string()
+    } else {
+        format!("{label} {suffix}")
+    }
+}
+
+/// Adds the runtime options of each sanitizer to a launch environment,
+/// leaving any the task sets itself alone.
+pub fn add_sanitizer_env(env: &mut Vec<(String, String)>, sanitizers: &[&str]) {
+    for (_, variable, options) in SANITIZERS
+        .iter()
+        .filter(|(name, _, _)| sanitizers.contains(name))
+    {
+        if !env.iter().any(|(key, _)| key == variable) {
+            env.push((variable.to_string(), options.to_string()));
+        }
     }
 }
 
+/// The library build mode (`dll`, `shared` or `dynamic`) a task's arguments
+/// select, if any. The last `-build-mode:` wins, as it does for the compiler.
+pub fn library_build_mode(args: &[String]) -> Option<&str> {
+    args.iter()
+        .rev()
+        .find_map(|arg| arg.strip_prefix("-build-mode:"))
+        .filter(|mode| LIBRARY_BUILD_MODES.contains(mode))
+}
+
+/// Whether a task's arguments build something the debugger can launch: an
+/// executable, a test runner or, through a host program, a library. Object
+/// files, static libraries, assembly and LLVM IR are not, so tasks building
+/// them get no debug scenario. The last `-build-mode:` wins.
+pub fn is_debuggable_build_mode(args: &[String]) -> bool {
+    match args
+        .iter()
+        .rev()
+        .find_map(|arg| arg.strip_prefix("-build-mode:"))
+    {
+        Some(mode) => matches!(mode, "exe" | "test") || LIBRARY_BUILD_MODES.contains(&mode),
+        None => true,
+    }
+}
+
+/// `path` as an absolute path, resolving relative paths against `cwd`.
+pub fn resolve_task_path(path: &str, cwd: &str, sep: &str) -> String {
+    if is_absolute_path(path) {
+        path.to_string()
+    } else {
+        format!("{cwd}{sep}{path}")
+    }
+}
+
+/// LLDB command that adds `library` to the target before launch, so its
+/// symbols are loaded and breakpoints in it resolve before the host program
+/// gets around to loading it.
+pub fn preload_library_command(library: &str) -> String {
+    format!("target modules add \"{}\"", library.replace('"', "\\\""))
+}
+
+/// The file name of `command` without its directory or a trailing `.exe`, so
+/// `/opt/odin/odin`, `$ODIN_ROOT/odin` and `C:\odin\odin.exe` all become
+/// `odin`.
+pub fn command_base_name(command: &str) -> &str {
+    let name = command.rsplit(['/', '\\']).next().unwrap_or(command).trim();
+    match name.len().checked_sub(".exe".len()) {
+        Some(stem) if name.is_char_boundary(stem) && name[stem..].eq_ignore_ascii_case(".exe") => {
+            &name[..stem]
+        }
+        _ => name,
+    }
+}
+
+/// Whether a task's `command` runs the Odin compiler, either directly or
+/// through one of the configured `wrapper_commands`. Both sides are compared
+/// by base name, ignoring ASCII case, so `./build.sh` matches a wrapper
+/// configured as `build.sh` and `C:\odin\ODIN.EXE` is the compiler.
+pub fn is_odin_command(command: &str, wrapper_commands: &[String]) -> bool {
+    let name = command_base_name(command);
+    name.eq_ignore_ascii_case("odin")
+        || wrapper_commands
+            .iter()
+            .any(|wrapper| command_base_name(wrapper).eq_ignore_ascii_case(name))
+}
+
+/// Rewrites the compiler flags of a run or test task for a debug build.
+/// `-out:` and `-build-mode:` are dropped because the debug build picks its
+/// own output path and mode, and `-o:` becomes `-o:none` when
+/// `downgrade_optimization` is set. Everything else, such as `-define:`,
+/// `-collection:`, `-target:` and `-vet`, is kept as written.
+pub fn sanitize_debug_build_args(args: &[String], downgrade_optimization: bool) -> Vec<String> {
+    args.iter()
+        .filter(|arg| !arg.starts_with("-out:") && !arg.starts_with("-build-mode:"))
+        .map(|arg| match arg.strip_prefix("-o:") {
+            Some(_) if downgrade_optimization => "-o:none".to_string(),
+            _ => arg.clone(),
+        })
+        .collect()
+}
+
+/// Whether `path` is absolute on either Unix (`/tmp`) or Windows (`C:\tmp`,
+/// `\\server\share`).
+pub fn is_absolute_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with(['/', '\\'])
+        || (bytes.len() >= 3
+            && bytes[0].is_ascii_alphabetic()
+            && bytes[1] == b':'
+            && matches!(bytes[2], b'/' | b'\\'))
+}
+
+/// A stable directory name for the project built from `cwd`: its last path
+/// component plus a hash of the full path, so two checkouts of the same
+/// project never share debug builds.
+pub fn debug_project_key(cwd: &str) -> String {
+    // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases.
+    let hash = cwd.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    let name: String = cwd
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() {
+        format!("{hash:016x}")
+    } else {
+        format!("{name}-{hash:016x}")
+    }
+}
+
+/// The directory debug builds of tasks run from `cwd` are written to: the
+/// configured directory, resolved against `cwd` when relative, or a
+/// per-project directory under [`DEFAULT_DEBUG_OUTPUT_DIR`] in `work_dir`.
+/// When the work dir is unknown, builds go to [`DEFAULT_DEBUG_OUTPUT_DIR`]
+/// in `cwd` instead, or relative to wherever the build runs if that is
+/// unknown too, never to a directory at the filesystem root.
+pub fn debug_output_dir(configured: Option<&str>, work_dir: &str, cwd: &str, sep: &str) -> String {
+    let trim = |path: &str| {
+        let trimmed = path.trim_end_matches(['/', '\\']);
+        if trimmed.is_empty() { path } else { trimmed }.to_string()
+    };
+    match configured {
+        Some(dir) if is_absolute_path(dir) => trim(dir),
+        Some(dir) if cwd.trim().is_empty() => trim(dir),
+        Some(dir) => format!("{}{sep}{}", trim(cwd), trim(dir)),
+        None if work_dir.trim().is_empty() && cwd.trim().is_empty() => {
+            DEFAULT_DEBUG_OUTPUT_DIR.to_string()
+        }
+        None if work_dir.trim().is_empty() => {
+            format!("{}{sep}{DEFAULT_DEBUG_OUTPUT_DIR}", trim(cwd))
+        }
+        None => format!(
+            "{}{sep}{DEFAULT_DEBUG_OUTPUT_DIR}{sep}{}",
+            trim(work_dir),
+            debug_project_key(cwd)
+        ),
+    }
+}
+
+/// Debug builds not rebuilt for this long are pruned. Every debug session
+/// rebuilds its binary first, so an old build belongs to a task that has not
+/// been debugged for a week, most likely one that was renamed or removed.
+pub const DEBUG_OUTPUT_MAX_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Entries of a debug output directory, given as `(name, modified_secs)`,
+/// that were last written more than [`DEBUG_OUTPUT_MAX_AGE_SECS`] before
+/// `now_secs`. The `live` build and its companion files such as
+/// `debug_build-app.pdb` are always kept, and files without
+/// [`DEBUG_OUTPUT_PREFIX`] are never considered stale.
+pub fn stale_debug_outputs(entries: &[(String, u64)], live: &str, now_secs: u64) -> Vec<String> {
+    let stem = |name: &str| name.split('.').next().unwrap_or_default().to_string();
+    let live = stem(live);
+    entries
+        .iter()
+        .filter(|(name, _)| name.starts_with(DEBUG_OUTPUT_PREFIX) && stem(name) != live)
+        .filter(|(_, modified)| now_secs.saturating_sub(*modified) > DEBUG_OUTPUT_MAX_AGE_SECS)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Environment variable that carries a run task's program arguments through
+/// the debug build template to `run_dap_locator`. The compiler never sees the
+/// arguments themselves, and the variable is removed before launching.
+pub const PROGRAM_ARGS_ENV: &str = "ZED_ODIN_PROGRAM_ARGS";
+
+/// Splits `odin run` arguments at the first `--` into compiler arguments and
+/// the arguments meant for the program.
+pub fn split_program_args(args: &[String]) -> (Vec<String>, Vec<String>) {
+    match args.iter().position(|arg| arg == "--") {
+        Some(separator) => (args[..separator].to_vec(), args[separator + 1..].to_vec()),
+        None => (args.to_vec(), Vec::new()),
+    }
+}
+
+pub fn encode_program_args(args: &[String]) -> String {
+    serde_json::to_string(args).unwrap_or_default()
+}
+
+/// Removes [`PROGRAM_ARGS_ENV`] from `env` and decodes the arguments it
+/// carried. A missing or malformed value yields no arguments.
+pub fn take_program_args(env: &mut Vec<(String, String)>) -> Vec<String> {
+    let mut args = Vec::new();
+    env.retain(|(key, value)| {
+        if key != PROGRAM_ARGS_ENV {
+            return true;
+        }
+        args = serde_json::from_str(value).unwrap_or_default();
+        false
+    });
+    args
+}
+
 #[derive(Clone, Debug)]
 pub struct Release {
     pub version: String,
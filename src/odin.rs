@@ -1,4 +1,4 @@
-use std::fs;
+use std::{collections::HashMap, fs, sync::Mutex};
 use zed::{
     BuildTaskDefinition, BuildTaskDefinitionTemplatePayload, BuildTaskTemplate, DebugRequest,
     DebugScenario, LanguageServerId, LaunchRequest, TaskTemplate, Worktree,
@@ -7,13 +7,123 @@ use zed_extension_api::{
     self as zed,
     lsp::{Completion, CompletionKind, Symbol, SymbolKind},
     serde_json,
-    settings::LspSettings,
+    settings::{CommandSettings, LspSettings},
     Architecture, CodeLabel, CodeLabelSpan, DebugConfig, Os, Result,
 };
 
 struct OdinExtension {
     cached_binary: Option<CachedBinary>,
     lldb_script: Option<String>,
+    /// Debug settings from the worktree whose settings were read last.
+    debug: DebugSettings,
+    /// The debug build launched last, attached to when no process is given.
+    last_debug_build: Option<String>,
+    /// Settings snapshots keyed by worktree id.
+    settings: HashMap<u64, SettingsSnapshot>,
+    /// The `detail` OLS sent with the latest completion of each name and kind.
+    /// Symbols carry only a name and kind, so symbol labels borrow it from here.
+    completion_details: Mutex<HashMap<(DetailKind, String), String>>,
+}
+
+/// What a remembered completion detail describes. A detail is only reused for
+/// a symbol of the same kind, so a variable never shows a proc signature as
+/// its type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum DetailKind {
+    Proc,
+    Variable,
+    Field,
+    Constant,
+    Struct,
+    Enum,
+    TypeParameter,
+}
+
+impl DetailKind {
+    fn of_completion(kind: CompletionKind) -> Option<Self> {
+        use CompletionKind::*;
+        Some(match kind {
+            Function | Method | Constructor => DetailKind::Proc,
+            Variable => DetailKind::Variable,
+            Field | Property => DetailKind::Field,
+            Constant => DetailKind::Constant,
+            Struct => DetailKind::Struct,
+            Enum => DetailKind::Enum,
+            TypeParameter => DetailKind::TypeParameter,
+            _ => return None,
+        })
+    }
+
+    fn of_symbol(kind: SymbolKind) -> Option<Self> {
+        use SymbolKind::*;
+        Some(match kind {
+            Function | Method | Constructor => DetailKind::Proc,
+            Variable => DetailKind::Variable,
+            Field | Property => DetailKind::Field,
+            Constant => DetailKind::Constant,
+            Struct => DetailKind::Struct,
+            Enum => DetailKind::Enum,
+            TypeParameter => DetailKind::TypeParameter,
+            _ => return None,
+        })
+    }
+}
+
+/// The `lsp.ols` settings of one worktree, read once per language server start
+/// so the binary, the LLDB script and the options it is given all agree.
+/// Refreshed when the server starts and when Zed reports changed settings.
+#[derive(Default)]
+struct SettingsSnapshot {
+    binary: Option<CommandSettings>,
+    initialization_options: Option<serde_json::Value>,
+    settings: Option<serde_json::Value>,
+    lldb_script: Option<String>,
+    debug: DebugSettings,
+    /// OLS release of the binary the language server was started with,
+    /// `None` for custom and `PATH` binaries whose release is unknown.
+    ols_release: Option<String>,
+}
+
+impl SettingsSnapshot {
+    fn read(language_server_id: &LanguageServerId, worktree: &Worktree) -> Self {
+        let Ok(lsp_settings) = LspSettings::for_worktree(language_server_id.as_ref(), worktree)
+        else {
+            return Self::default();
+        };
+        let (platform, _) = zed::current_platform();
+        let os = OdinExtension::platform_key(platform);
+
+        let mut binary = lsp_settings.binary;
+        let mut settings = lsp_settings.settings;
+        let mut initialization_options = lsp_settings.initialization_options;
+        if let Some(options) = initialization_options.as_mut() {
+            apply_platform_overrides(options, os);
+        }
+        if let Some(settings) = settings.as_mut() {
+            apply_platform_overrides(settings, os);
+            if let Some(overrides) = settings
+                .as_object_mut()
+                .and_then(|settings| settings.remove(PLATFORM_BINARY_KEY))
+            {
+                let mut merged = serde_json::to_value(&binary).unwrap_or_default();
+                deep_merge(&mut merged, overrides);
+                if let Ok(merged) = serde_json::from_value(merged) {
+                    binary = merged;
+                }
+            }
+        }
+
+        let lldb_script = lldb_script_from_settings(settings.as_ref())
+            .and_then(|raw_path| worktree.read_text_file(&raw_path).ok());
+        Self {
+            binary,
+            initialization_options,
+            lldb_script,
+            debug: debug_settings_from(settings.as_ref()),
+            settings,
+            ols_release: None,
+        }
+    }
 }
 
 struct CachedBinary {
@@ -21,18 +131,39 @@ struct CachedBinary {
     path: String,
 }
 
+mod labels;
 mod logic;
+use labels::{
+    enum_or_union_label, import_label, keyword_label, package_label, proc_label, snippet_label,
+    split_container, type_label, type_name_label, type_parameter_label, variable_label,
+    with_container, Label, LabelSpan, COLLECTION_HIGHLIGHT,
+};
 use logic::{
-    debug_output_name, lldb_prerun_command, lldb_script_from_settings,
-    merged_initialization_options, release_tag_from_settings, resolve_ols_binary,
-    strip_extension_settings, use_path_binary, Host, Release, ReleaseAsset, ResolveInputs,
-    LAST_RELEASE_CHECK_FILE,
+    add_sanitizer_env, apply_platform_overrides, apply_test_debug_defines, attach_config,
+    attach_target, auto_collections, auto_collections_from_settings, collection_imports,
+    configured_collections, debug_output_dir, debug_output_name, debug_settings_from, deep_merge,
+    encode_program_args, gitmodule_top_dirs, inject_collections, inject_pre_run_commands,
+    installed_release, is_debuggable_build_mode, is_odin_command, library_build_mode,
+    lldb_prerun_command, lldb_script_from_settings, merged_initialization_options,
+    panic_breakpoint_command, preload_library_command, probe_top_dirs, release_tag_from_settings,
+    requested_sanitizers, resolve_ols_binary, resolve_task_path, sanitize_debug_build_args,
+    sanitizer_label, split_program_args, stale_debug_outputs, strip_extension_settings,
+    take_program_args, use_path_binary, versioned_defaults_for, DebugSettings, Host, Release,
+    ReleaseAsset, ResolveInputs, LAST_RELEASE_CHECK_FILE, PLATFORM_BINARY_KEY, PROGRAM_ARGS_ENV,
 };
 
 const GITHUB_REPO: &str = "DanielGavin/ols";
 
+/// Cap on remembered completion details; the cache starts over once full.
+const MAX_COMPLETION_DETAILS: usize = 4096;
+
 const ODIN_SCRIPT: &str = include_str!("../resources/lldb/odin.py");
 
+/// Worktree files scanned for `import "name:..."` lines when `auto_collections`
+/// is enabled. Extensions can only read files by path, so this sticks to the
+/// usual entry points.
+const AUTO_COLLECTION_SOURCES: &[&str] = &["main.odin", "src/main.odin"];
+
 impl OdinExtension {
     fn exe_suffix(platform: Os) -> &'static str {
         match platform {
@@ -41,6 +172,23 @@ impl OdinExtension {
         }
     }
 
+    fn library_suffix(platform: Os) -> &'static str {
+        match platform {
+            Os::Mac => ".dylib",
+            Os::Linux => ".so",
+            Os::Windows => ".dll",
+        }
+    }
+
+    /// Key of the `platform` override block that applies to `platform`.
+    fn platform_key(platform: Os) -> &'static str {
+        match platform {
+            Os::Mac => "mac",
+            Os::Linux => "linux",
+            Os::Windows => "windows",
+        }
+    }
+
     fn path_separator(platform: Os) -> &'static str {
         match platform {
             Os::Windows => "\\",
@@ -57,6 +205,72 @@ impl OdinExtension {
             .unwrap_or_else(|| ODIN_SCRIPT.to_string())
     }
 
+    /// Where debug builds of tasks run from `cwd` go. The default lives in the
+    /// extension's work dir, the only place the extension can create
+    /// directories and prune old builds. If the work dir cannot be found,
+    /// builds go under `cwd` instead.
+    fn debug_output_dir(&self, cwd: &str, platform: Os) -> String {
+        let work_dir = std::env::current_dir()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        debug_output_dir(
+            self.debug.output_dir.as_deref(),
+            &work_dir,
+            cwd,
+            Self::path_separator(platform),
+        )
+    }
+
+    /// Removes debug builds next to `program` that have not been rebuilt for a
+    /// week, e.g. builds of files or tests that have since been renamed.
+    fn prune_debug_outputs(program: &str, separator: &str) {
+        let Some((dir, live)) = program.rsplit_once(separator) else {
+            return;
+        };
+        let (Ok(entries), Some(now)) = (fs::read_dir(dir), Self::unix_time_now()) else {
+            return;
+        };
+        let entries: Vec<(String, u64)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                let modified = modified
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .ok()?
+                    .as_secs();
+                Some((entry.file_name().to_string_lossy().into_owned(), modified))
+            })
+            .collect();
+        for stale in stale_debug_outputs(&entries, live, now) {
+            fs::remove_file(format!("{dir}{separator}{stale}")).ok();
+        }
+    }
+
+    /// Adds the Odin formatter, and the panic breakpoint unless turned off,
+    /// to the `preRunCommands` of a debug config, ahead of whatever commands
+    /// it already runs.
+    fn inject_odin_commands(&self, config: &mut serde_json::Map<String, serde_json::Value>) {
+        let mut commands = vec![lldb_prerun_command(&self.resolve_lldb_script())];
+        if self.debug.break_on_panic {
+            commands.push(panic_breakpoint_command());
+        }
+        inject_pre_run_commands(config, commands);
+    }
+
+    /// The CodeLLDB config of a locator scenario: the Odin commands, followed
+    /// for library builds by the command that preloads `library`.
+    fn locator_config(&self, library: Option<&str>) -> serde_json::Map<String, serde_json::Value> {
+        let mut config = serde_json::Map::new();
+        if let Some(library) = library {
+            config.insert(
+                "preRunCommands".to_string(),
+                serde_json::json!([preload_library_command(library)]),
+            );
+        }
+        self.inject_odin_commands(&mut config);
+        config
+    }
+
     fn ols_binary_name(&self, platform: Os, arch: Architecture) -> Option<String> {
         let arch: &str = match arch {
             zed::Architecture::Aarch64 => "arm64",
@@ -81,29 +295,96 @@ impl OdinExtension {
             .map(|duration| duration.as_secs())
     }
 
-    fn language_server_binary_path(
+    /// Re-reads the settings of `worktree`, replacing its snapshot.
+    fn refresh_settings(
         &mut self,
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
-    ) -> Result<String> {
-        let lsp_settings = LspSettings::for_worktree(language_server_id.as_ref(), worktree).ok();
+    ) -> &mut SettingsSnapshot {
+        let mut snapshot = SettingsSnapshot::read(language_server_id, worktree);
+        if let Some(previous) = self.settings.get(&worktree.id()) {
+            snapshot.ols_release = previous.ols_release.clone();
+        }
+        if lldb_script_from_settings(snapshot.settings.as_ref()).is_some() {
+            self.lldb_script = snapshot.lldb_script.clone();
+        }
+        self.debug = snapshot.debug.clone();
+        self.settings.insert(worktree.id(), snapshot);
+        self.settings_for(language_server_id, worktree)
+    }
+
+    /// The settings snapshot of `worktree`, read on first use.
+    fn settings_for(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> &mut SettingsSnapshot {
+        self.settings
+            .entry(worktree.id())
+            .or_insert_with(|| SettingsSnapshot::read(language_server_id, worktree))
+    }
 
-        if let Some(raw_path) =
-            lldb_script_from_settings(lsp_settings.as_ref().and_then(|s| s.settings.as_ref()))
+    /// Derives collections from the worktree layout and adds the ones that
+    /// resolve to a top-level directory to `options`.
+    fn inject_auto_collections(worktree: &Worktree, options: &mut serde_json::Value) {
+        let mut imports = Vec::new();
+        for source in AUTO_COLLECTION_SOURCES {
+            if let Ok(text) = worktree.read_text_file(source) {
+                imports.extend(collection_imports(&text));
+            }
+        }
+        let mut top_dirs = worktree
+            .read_text_file(".gitmodules")
+            .map(|gitmodules| gitmodule_top_dirs(&gitmodules))
+            .unwrap_or_default();
+        top_dirs.extend(probe_top_dirs(&imports, |path| {
+            worktree.read_text_file(path).is_ok()
+        }));
+
+        let mut configured = configured_collections(options);
+        if let Some(ols_json) = worktree
+            .read_text_file("ols.json")
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
         {
-            self.lldb_script = worktree.read_text_file(&raw_path).ok();
+            configured.extend(configured_collections(&ols_json));
         }
 
-        if let Some(path) = lsp_settings
+        let derived = auto_collections(&imports, &top_dirs, &configured);
+        if !derived.found.is_empty() {
+            eprintln!("odin: adding collections {}", derived.found.join(", "));
+        }
+        for name in &derived.unresolved {
+            eprintln!(
+                "odin: import prefix `{name}:` has no collection; add one to `collections` if it lives outside the worktree"
+            );
+        }
+        let (platform, _) = zed::current_platform();
+        inject_collections(
+            options,
+            &derived.found,
+            &worktree.root_path(),
+            Self::path_separator(platform),
+        );
+    }
+
+    fn language_server_binary_path(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<String> {
+        let snapshot = self.settings_for(language_server_id, worktree);
+        snapshot.ols_release = None;
+
+        if let Some(path) = snapshot
+            .binary
             .as_ref()
-            .and_then(|settings| settings.binary.as_ref())
             .and_then(|binary| binary.path.clone())
         {
             return Ok(path);
         }
 
-        let release_tag =
-            release_tag_from_settings(lsp_settings.as_ref().and_then(|s| s.settings.as_ref()));
+        let release_tag = release_tag_from_settings(snapshot.settings.as_ref());
 
         if use_path_binary(release_tag.as_deref()) {
             if let Some(path) = worktree.which(language_server_id.as_ref()) {
@@ -136,6 +417,8 @@ impl OdinExtension {
 
         let mut host = ZedHost { language_server_id };
         let path = resolve_ols_binary(&mut host, &inputs)?;
+        self.settings_for(language_server_id, worktree).ols_release =
+            installed_release(&path, Self::path_separator(platform)).map(str::to_string);
         self.cached_binary = Some(CachedBinary {
             release_tag,
             path: path.clone(),
@@ -224,43 +507,165 @@ impl Host for ZedHost<'_> {
 }
 
 impl OdinExtension {
-    fn is_integer_type(type_str: &str) -> bool {
-        matches!(
-            type_str,
-            // Basic signed integers
-            "int" | "i8" | "i16" | "i32" | "i64" | "i128" |
-            // Basic unsigned integers
-            "uint" | "u8" | "u16" | "u32" | "u64" | "u128" | "uintptr" |
-            // Integer aliases
-            "byte" | "rune" |
-            // Little-endian integers
-            "i16le" | "i32le" | "i64le" | "i128le" |
-            "u16le" | "u32le" | "u64le" | "u128le" |
-            // Big-endian integers
-            "i16be" | "i32be" | "i64be" | "i128be" |
-            "u16be" | "u32be" | "u64be" | "u128be"
-        )
+    fn code_label(label: Label) -> CodeLabel {
+        CodeLabel {
+            code: label.code,
+            spans: label
+                .spans
+                .into_iter()
+                .map(|span| match span {
+                    LabelSpan::Code(range) => CodeLabelSpan::code_range(range),
+                    LabelSpan::Literal { text, highlight } => {
+                        CodeLabelSpan::literal(text, highlight.map(str::to_string))
+                    }
+                })
+                .collect(),
+            filter_range: label.filter_range.into(),
+        }
     }
 
-    fn create_label(code: String, filter_len: usize) -> CodeLabel {
-        let code_len = code.len();
-        CodeLabel {
-            code,
-            spans: vec![CodeLabelSpan::code_range(0..code_len)],
-            filter_range: (0..filter_len).into(),
+    fn completion_label(completion: Completion) -> Option<Label> {
+        use CompletionKind::*;
+
+        let kind = completion.kind?;
+        let label = &completion.label;
+        let filter_len = label.len();
+
+        match kind {
+            Struct => Some(type_label(label, completion.detail.as_deref(), "struct")),
+
+            Function | Method | Constructor => Some(proc_label(
+                label,
+                completion.detail.as_deref(),
+                completion
+                    .label_details
+                    .as_ref()
+                    .and_then(|details| details.detail.as_deref()),
+                completion
+                    .label_details
+                    .as_ref()
+                    .and_then(|details| details.description.as_deref()),
+            )),
+
+            // OLS reports unions as enums too; the detail (e.g. "union { int, f32 }")
+            // tells them apart.
+            Enum => Some(enum_or_union_label(label, completion.detail.as_deref())),
+
+            Variable | Field => Some(variable_label(label, completion.detail.as_deref())),
+
+            Constant => {
+                let value = completion.detail.unwrap_or_else(|| "value".to_string());
+                Some(Label::plain(format!("{} :: {}", label, value), filter_len))
+            }
+
+            EnumMember => {
+                let code = format!(".{}", label);
+                Some(Label::with_span(code, 1..label.len() + 1, filter_len))
+            }
+
+            Property => {
+                let code = format!(".{}", label);
+                Some(Label::with_span(code, 1..label.len() + 1, filter_len))
+            }
+
+            TypeParameter => Some(type_parameter_label(label, completion.detail.as_deref())),
+
+            Keyword => Some(keyword_label(label)),
+
+            Snippet => Some(snippet_label(label, completion.detail.as_deref())),
+
+            Module => Some(import_label(
+                label,
+                &[
+                    completion.detail.as_deref(),
+                    completion
+                        .label_details
+                        .as_ref()
+                        .and_then(|details| details.description.as_deref()),
+                    completion
+                        .label_details
+                        .as_ref()
+                        .and_then(|details| details.detail.as_deref()),
+                ],
+            )),
+
+            _ => None,
         }
     }
 
-    fn create_label_with_span(
-        code: String,
-        span_range: std::ops::Range<usize>,
-        filter_len: usize,
-    ) -> CodeLabel {
-        CodeLabel {
-            code,
-            spans: vec![CodeLabelSpan::code_range(span_range)],
-            filter_range: (0..filter_len).into(),
+    fn remember_completion_detail(&self, kind: DetailKind, name: &str, detail: &str) {
+        let Ok(mut details) = self.completion_details.lock() else {
+            return;
+        };
+        let key = (kind, name.to_string());
+        if details.len() >= MAX_COMPLETION_DETAILS && !details.contains_key(&key) {
+            details.clear();
         }
+        details.insert(key, detail.to_string());
+    }
+
+    fn completion_detail(&self, kind: DetailKind, name: &str) -> Option<String> {
+        self.completion_details
+            .lock()
+            .ok()?
+            .get(&(kind, name.to_string()))
+            .cloned()
+    }
+
+    fn symbol_label(symbol: Symbol, completion_detail: Option<&str>) -> Option<Label> {
+        // NOTE: Symbol navigation has limited type information compared to completions.
+        // The LSP Symbol type only provides 'name' and 'kind', without detailed type info,
+        // so `completion_detail` is whatever OLS last sent when completing the same name
+        // as the same kind of symbol.
+        // Workspace symbols come qualified by their package or type (`renderer.draw`,
+        // `Vec3.x`), which is shown as the container.
+
+        use SymbolKind::*;
+
+        let (container, name) = split_container(&symbol.name);
+
+        let (label, container_highlight) = match symbol.kind {
+            Function | Constructor => (
+                proc_label(name, completion_detail, None, None),
+                COLLECTION_HIGHLIGHT,
+            ),
+            Method => (proc_label(name, completion_detail, None, None), "type"),
+            Variable => (
+                variable_label(name, completion_detail),
+                COLLECTION_HIGHLIGHT,
+            ),
+            // Struct and union fields
+            Field | Property => (variable_label(name, completion_detail), "type"),
+            Constant => (
+                match completion_detail {
+                    Some(value) => Label::plain(format!("{name} :: {value}"), name.len()),
+                    None => Label::plain(name.to_string(), name.len()),
+                },
+                COLLECTION_HIGHLIGHT,
+            ),
+            EnumMember => (
+                Label::with_span(format!(".{name}"), 1..name.len() + 1, name.len()),
+                "type",
+            ),
+            Struct => (
+                type_label(name, completion_detail, "struct"),
+                COLLECTION_HIGHLIGHT,
+            ),
+            // OLS sends both enums and unions as Enum kind; the remembered completion
+            // detail tells them apart once the name has been completed.
+            Enum => (
+                enum_or_union_label(name, completion_detail),
+                COLLECTION_HIGHLIGHT,
+            ),
+            Interface => (type_name_label(name), COLLECTION_HIGHLIGHT),
+            TypeParameter => (
+                type_parameter_label(name, completion_detail),
+                COLLECTION_HIGHLIGHT,
+            ),
+            Package | Namespace | Module => (package_label(name), COLLECTION_HIGHLIGHT),
+            _ => return None,
+        };
+        Some(with_container(label, container, container_highlight))
     }
 }
 
@@ -269,6 +674,10 @@ impl zed::Extension for OdinExtension {
         Self {
             cached_binary: None,
             lldb_script: None,
+            debug: DebugSettings::default(),
+            last_debug_build: None,
+            settings: HashMap::new(),
+            completion_details: Mutex::default(),
         }
     }
 
@@ -277,15 +686,15 @@ impl zed::Extension for OdinExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<zed::Command> {
-        let binary_settings = LspSettings::for_worktree(language_server_id.as_ref(), worktree)
-            .ok()
-            .and_then(|settings| settings.binary);
+        let binary_settings = self
+            .refresh_settings(language_server_id, worktree)
+            .binary
+            .as_ref();
         let args = binary_settings
-            .as_ref()
             .and_then(|binary| binary.arguments.clone())
             .unwrap_or_default();
         let env = binary_settings
-            .and_then(|binary| binary.env)
+            .and_then(|binary| binary.env.clone())
             .map(|env| env.into_iter().collect())
             .unwrap_or_default();
 
@@ -302,10 +711,26 @@ impl zed::Extension for OdinExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        let user_options = LspSettings::for_worktree(language_server_id.as_ref(), worktree)
-            .ok()
-            .and_then(|lsp_settings| lsp_settings.initialization_options.clone());
-        Ok(Some(merged_initialization_options(user_options)))
+        let snapshot = self.settings_for(language_server_id, worktree);
+        let user_options = snapshot.initialization_options.clone();
+        let release = snapshot.ols_release.as_deref();
+        let versioned = versioned_defaults_for(release);
+        if !versioned.is_empty() {
+            eprintln!(
+                "odin: OLS {} supports newer defaults, enabling: {}",
+                release.unwrap_or("(unknown release)"),
+                versioned.join(", ")
+            );
+        }
+        let auto_collections = auto_collections_from_settings(snapshot.settings.as_ref());
+        let (mut options, notices) = merged_initialization_options(user_options, release);
+        for notice in notices {
+            eprintln!("odin: {notice}");
+        }
+        if auto_collections {
+            Self::inject_auto_collections(worktree, &mut options);
+        }
+        Ok(Some(options))
     }
 
     fn language_server_workspace_configuration(
@@ -313,9 +738,12 @@ impl zed::Extension for OdinExtension {
         language_server_id: &LanguageServerId,
         worktree: &Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        let mut settings = LspSettings::for_worktree(language_server_id.as_ref(), worktree)
-            .ok()
-            .and_then(|lsp_settings| lsp_settings.settings.clone())
+        // Zed asks for the workspace configuration again whenever the user's
+        // settings change, so this is where a stale snapshot gets replaced.
+        let mut settings = self
+            .refresh_settings(language_server_id, worktree)
+            .settings
+            .clone()
             .unwrap_or_default();
         strip_extension_settings(&mut settings);
         Ok(Some(settings))
@@ -326,92 +754,13 @@ impl zed::Extension for OdinExtension {
         _language_server_id: &LanguageServerId,
         completion: Completion,
     ) -> Option<CodeLabel> {
-        use CompletionKind::*;
-
-        let kind = completion.kind?;
-        let label = &completion.label;
-        let filter_len = label.len();
-
-        match kind {
-            Struct => {
-                let code = match &completion.detail {
-                    Some(detail) if detail.starts_with('[') || detail.starts_with("distinct") => {
-                        format!("{} :: {}", label, detail)
-                    }
-                    _ => format!("{} :: struct", label),
-                };
-                Some(Self::create_label(code, filter_len))
-            }
-
-            Enum => {
-                let code = match &completion.detail {
-                    // OLS sends union type info in detail field (e.g., "union { int, f32 }")
-                    // We can detect and display it correctly here
-                    Some(detail) if detail.contains("union") => {
-                        format!("{} :: union", label)
-                    }
-                    Some(detail) if Self::is_integer_type(detail) => {
-                        format!("{} :: enum {}", label, detail)
-                    }
-                    _ => format!("{} :: enum", label),
-                };
-                Some(Self::create_label(code, filter_len))
-            }
-
-            Variable | Field => {
-                let type_name = completion.detail.unwrap_or_else(|| "type".to_string());
-                Some(Self::create_label(
-                    format!("{}: {}", label, type_name),
-                    filter_len,
-                ))
-            }
-
-            Constant => {
-                let value = completion.detail.unwrap_or_else(|| "value".to_string());
-                Some(Self::create_label(
-                    format!("{} :: {}", label, value),
-                    filter_len,
-                ))
-            }
-
-            EnumMember => {
-                let code = format!(".{}", label);
-                Some(Self::create_label_with_span(
-                    code,
-                    1..label.len() + 1,
-                    filter_len,
-                ))
-            }
-
-            Property => {
-                let code = format!(".{}", label);
-                Some(Self::create_label_with_span(
-                    code,
-                    1..label.len() + 1,
-                    filter_len,
-                ))
-            }
-
-            Keyword => Some(CodeLabel {
-                code: label.clone(),
-                spans: vec![CodeLabelSpan::literal(
-                    label.clone(),
-                    Some("keyword".to_string()),
-                )],
-                filter_range: (0..filter_len).into(),
-            }),
-
-            Module => {
-                let code = format!("package {}", label);
-                Some(Self::create_label_with_span(
-                    code,
-                    8..label.len() + 8,
-                    filter_len,
-                ))
-            }
-
-            _ => None,
+        if let (Some(kind), Some(detail)) = (
+            completion.kind.and_then(DetailKind::of_completion),
+            &completion.detail,
+        ) {
+            self.remember_completion_detail(kind, &completion.label, detail);
         }
+        Self::completion_label(completion).map(Self::code_label)
     }
 
     fn label_for_symbol(
@@ -419,27 +768,10 @@ impl zed::Extension for OdinExtension {
         _language_server_id: &LanguageServerId,
         symbol: Symbol,
     ) -> Option<CodeLabel> {
-        // NOTE: Symbol navigation has limited type information compared to completions.
-        // The LSP Symbol type only provides 'name' and 'kind', without detailed type info.
-
-        use SymbolKind::*;
-
-        let name = &symbol.name;
-        let filter_len = name.len();
-
-        match symbol.kind {
-            Function => Some(Self::create_label(format!("{} :: proc", name), filter_len)),
-            Variable => Some(Self::create_label(format!("{}: type", name), filter_len)),
-            Struct => Some(Self::create_label(
-                format!("{} :: struct", name),
-                filter_len,
-            )),
-            // OLS sends both enums and unions as Enum kind (cannot distinguish in symbols)
-            Enum => Some(Self::create_label(format!("{} :: enum", name), filter_len)),
-            // Struct and union fields
-            Field => Some(Self::create_label(format!("{}: type", name), filter_len)),
-            _ => None,
-        }
+        let (_, name) = split_container(&symbol.name);
+        let detail =
+            DetailKind::of_symbol(symbol.kind).and_then(|kind| self.completion_detail(kind, name));
+        Self::symbol_label(symbol, detail.as_deref()).map(Self::code_label)
     }
 
     fn dap_config_to_scenario(&mut self, config: DebugConfig) -> Result<DebugScenario, String> {
@@ -462,10 +794,19 @@ impl zed::Extension for OdinExtension {
                 }
             }
             DebugRequest::Attach(attach) => {
-                config_map.insert("request".to_string(), serde_json::json!("attach"));
-                config_map.insert("pid".to_string(), serde_json::json!(&attach.process_id));
+                let target = attach_target(
+                    attach.process_id,
+                    &self.debug,
+                    self.last_debug_build.as_deref(),
+                )
+                .ok_or(
+                    "Attaching needs a process id, debug_attach_program in lsp.ols.settings, \
+                     or a previous Odin debug session",
+                )?;
+                config_map = attach_config(&target);
             }
         }
+        self.inject_odin_commands(&mut config_map);
 
         if let Some(stop_on_entry) = config.stop_on_entry {
             config_map.insert("stopOnEntry".to_string(), serde_json::json!(stop_on_entry));
@@ -491,22 +832,64 @@ impl zed::Extension for OdinExtension {
         resolved_label: String,
         debug_adapter_name: String,
     ) -> Option<DebugScenario> {
-        let is_run = build_task.command == "odin" && build_task.args.first() == Some(&"run".into());
-        let is_test =
-            build_task.command == "odin" && build_task.args.first() == Some(&"test".into());
+        let is_odin = is_odin_command(&build_task.command, &self.debug.wrapper_commands);
+        let is_run = is_odin && build_task.args.first() == Some(&"run".into());
+        let is_test = is_odin && build_task.args.first() == Some(&"test".into());
+        let is_build = is_odin && build_task.args.first() == Some(&"build".into());
 
-        if !is_run && !is_test {
+        if !is_run && !is_test && !is_build {
             return None;
         }
+        // Object files, static libraries and the like have nothing to launch.
+        if !is_debuggable_build_mode(&build_task.args) {
+            return None;
+        }
+
+        // Sanitized builds get their own label, and with it their own binary.
+        let sanitizers = requested_sanitizers(&build_task.args);
+        let resolved_label = sanitizer_label(&resolved_label, &sanitizers);
 
-        // Convert "odin run" to "odin build" with -debug flag
-        let mut build_args = build_task.args.clone();
+        // Libraries are debugged by launching the host program that loads them.
+        let library_mode = library_build_mode(&build_task.args).map(str::to_string);
+        if library_mode.is_some() && self.debug.host_program.is_none() {
+            return None;
+        }
+
+        // Convert "odin run" to "odin build" with -debug flag. Anything after
+        // "--" belongs to the program, so it travels to the launch request
+        // instead of the compiler.
+        let (build_args, program_args) = split_program_args(&build_task.args);
+        let mut build_args =
+            sanitize_debug_build_args(&build_args, self.debug.downgrade_optimization);
         build_args[0] = "build".to_string();
+        let mut build_env = build_task.env.clone();
+        if !program_args.is_empty() {
+            build_env.push((
+                PROGRAM_ARGS_ENV.to_string(),
+                encode_program_args(&program_args),
+            ));
+        }
 
         // Add -out flag to control output name
         let (platform, _) = zed::current_platform();
-        let out_name = debug_output_name(&resolved_label, Self::exe_suffix(platform));
-        build_args.push(format!("-out:{}", out_name));
+        let out_suffix = if library_mode.is_some() {
+            Self::library_suffix(platform)
+        } else {
+            Self::exe_suffix(platform)
+        };
+        let out_name = debug_output_name(&resolved_label, out_suffix);
+        let out_path = match build_task.cwd.as_deref() {
+            Some(cwd) => {
+                let dir = self.debug_output_dir(cwd, platform);
+                // Odin does not create missing output directories. This only
+                // succeeds inside the extension's work dir; configured
+                // directories have to exist already.
+                fs::create_dir_all(&dir).ok();
+                format!("{dir}{}{out_name}", Self::path_separator(platform))
+            }
+            None => out_name,
+        };
+        build_args.push(format!("-out:{}", out_path));
 
         // Add -debug flag if not present
         if !build_args.contains(&"-debug".into()) {
@@ -514,7 +897,11 @@ impl zed::Extension for OdinExtension {
         }
 
         if is_test {
-            build_args.push("-build-mode:test".into())
+            build_args.push("-build-mode:test".into());
+            apply_test_debug_defines(&mut build_args, self.debug.test_track_memory);
+        }
+        if let Some(mode) = &library_mode {
+            build_args.push(format!("-build-mode:{mode}"));
         }
 
         // Create the build task template
@@ -526,27 +913,20 @@ impl zed::Extension for OdinExtension {
             },
             command: build_task.command.clone(),
             args: build_args,
-            env: build_task.env.clone(),
+            env: build_env,
             cwd: build_task.cwd.clone(),
         };
 
-        let mut config_map = serde_json::Map::new();
-
-        let exec_command = lldb_prerun_command(&self.resolve_lldb_script());
-
-        config_map.insert(
-            "preRunCommands".to_string(),
-            serde_json::json!(vec![exec_command]),
-        );
-
-        let config = serde_json::to_string(&config_map).ok()?;
+        let library = library_mode.is_some().then_some(out_path.as_str());
+        let config = serde_json::to_string(&self.locator_config(library)).ok()?;
 
         // Update the task labels. The resulting label will be displayed as-is in
         // the F4 Debug menu and will have "Debug: " prepended to the label when
         // shown in the test gutter.
-        let label = if is_run {
+        let label = if is_run || is_build {
             resolved_label
                 .strip_prefix("run: ")
+                .or_else(|| resolved_label.strip_prefix("build: "))
                 .unwrap_or(&resolved_label)
                 .to_string()
         } else {
@@ -576,7 +956,7 @@ impl zed::Extension for OdinExtension {
         build_task: TaskTemplate,
     ) -> Result<DebugRequest, String> {
         // Only handle Odin build and test tasks
-        if build_task.command != "odin"
+        if !is_odin_command(&build_task.command, &self.debug.wrapper_commands)
             || build_task.args.is_empty()
             || !(build_task.args[0] == "build" || build_task.args[0] == "test")
         {
@@ -595,13 +975,31 @@ impl zed::Extension for OdinExtension {
         let cwd = build_task.cwd.as_ref().ok_or("No cwd in build task")?;
         let (platform, _) = zed::current_platform();
         let separator = Self::path_separator(platform);
-        let program = format!("{}{}{}", cwd, separator, output_name);
-
+        let output = resolve_task_path(&output_name, cwd, separator);
+        Self::prune_debug_outputs(&output, separator);
+
+        let mut envs: Vec<(String, String)> = build_task.env.into_iter().collect();
+        let mut args = take_program_args(&mut envs);
+        add_sanitizer_env(&mut envs, &requested_sanitizers(&build_task.args));
+
+        // A library is debugged through the host program that loads it.
+        let program = if library_build_mode(&build_task.args).is_some() {
+            let host = self
+                .debug
+                .host_program
+                .as_deref()
+                .ok_or("Set debug_host_program in lsp.ols.settings to debug a library build")?;
+            args.splice(0..0, self.debug.host_args.iter().cloned());
+            resolve_task_path(host, cwd, separator)
+        } else {
+            output
+        };
+        self.last_debug_build = Some(program.clone());
         let request = LaunchRequest {
             program,
             cwd: build_task.cwd,
-            args: vec![],
-            envs: build_task.env.into_iter().collect(),
+            args,
+            envs,
         };
 
         Ok(DebugRequest::Launch(request))
@@ -611,11 +1009,16 @@ impl zed::Extension for OdinExtension {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use zed::Extension as _;
 
     fn extension(lldb_script: Option<&str>) -> OdinExtension {
         OdinExtension {
             cached_binary: None,
             lldb_script: lldb_script.map(str::to_string),
+            debug: DebugSettings::default(),
+            last_debug_build: None,
+            settings: HashMap::new(),
+            completion_details: Mutex::default(),
         }
     }
 
@@ -631,6 +1034,98 @@ mod tests {
             "# custom marker\n"
         );
     }
+
+    #[test]
+    fn completion_details_are_only_reused_for_the_same_kind() {
+        let extension = extension(None);
+        let proc_kind = DetailKind::of_completion(CompletionKind::Function).unwrap();
+        extension.remember_completion_detail(proc_kind, "init", "proc(w: ^World)");
+
+        assert_eq!(DetailKind::of_symbol(SymbolKind::Method), Some(proc_kind));
+        assert_eq!(
+            extension.completion_detail(proc_kind, "init").as_deref(),
+            Some("proc(w: ^World)")
+        );
+        let variable_kind = DetailKind::of_symbol(SymbolKind::Variable).unwrap();
+        assert_eq!(extension.completion_detail(variable_kind, "init"), None);
+        assert_eq!(DetailKind::of_completion(CompletionKind::Keyword), None);
+    }
+
+    #[test]
+    fn library_scenarios_keep_the_preload_after_the_odin_commands() {
+        let extension = extension(Some("# custom marker\n"));
+        let config = extension.locator_config(Some("/out/debug_build-game.so"));
+
+        assert_eq!(
+            serde_json::Value::Object(config),
+            serde_json::json!({
+                "preRunCommands": [
+                    lldb_prerun_command("# custom marker\n"),
+                    panic_breakpoint_command(),
+                    preload_library_command("/out/debug_build-game.so"),
+                ],
+            })
+        );
+        assert_eq!(
+            extension.locator_config(None)["preRunCommands"],
+            serde_json::json!([
+                lldb_prerun_command("# custom marker\n"),
+                panic_breakpoint_command(),
+            ])
+        );
+    }
+
+    #[test]
+    fn builds_with_nothing_to_launch_get_no_scenario() {
+        let task = |mode: &str| TaskTemplate {
+            label: "build: game".to_string(),
+            command: "odin".to_string(),
+            args: vec![
+                "build".to_string(),
+                ".".to_string(),
+                format!("-build-mode:{mode}"),
+            ],
+            env: vec![],
+            cwd: Some("/game".to_string()),
+        };
+        let mut extension = extension(None);
+        for mode in ["obj", "lib", "static", "asm", "llvm-ir"] {
+            let scenario = extension.dap_locator_create_scenario(
+                "odin".to_string(),
+                task(mode),
+                "build: game".to_string(),
+                "CodeLLDB".to_string(),
+            );
+            assert!(scenario.is_none(), "{mode}");
+        }
+    }
+
+    #[test]
+    fn converted_launch_configs_load_the_formatter() {
+        let scenario = extension(Some("# custom marker\n"))
+            .dap_config_to_scenario(DebugConfig {
+                label: "Debug game".to_string(),
+                adapter: "CodeLLDB".to_string(),
+                request: DebugRequest::Launch(LaunchRequest {
+                    program: "/game/bin/game".to_string(),
+                    cwd: None,
+                    args: vec![],
+                    envs: vec![],
+                }),
+                stop_on_entry: None,
+            })
+            .unwrap();
+        let config: serde_json::Value = serde_json::from_str(&scenario.config).unwrap();
+
+        assert_eq!(config["program"], "/game/bin/game");
+        assert_eq!(
+            config["preRunCommands"],
+            serde_json::json!([
+                lldb_prerun_command("# custom marker\n"),
+                panic_breakpoint_command(),
+            ])
+        );
+    }
 }
 
 zed::register_extension!(OdinExtension);
@@ -0,0 +1,759 @@
+#[allow(dead_code)]
+#[path = "../src/labels.rs"]
+mod labels;
+
+use labels::*;
+
+fn code(label: &Label) -> &str {
+    &label.code
+}
+
+fn filter_text(label: &Label) -> &str {
+    &label.code[label.filter_range.clone()]
+}
+
+#[test]
+fn proc_labels_use_the_signature_from_detail() {
+    let label = proc_label("lerp", Some("proc(a, b: f32, t: f32) -> f32"), None, None);
+    assert_eq!(code(&label), "lerp :: proc(a, b: f32, t: f32) -> f32");
+    assert_eq!(filter_text(&label), "lerp");
+    assert_eq!(label.spans, vec![LabelSpan::Code(0..label.code.len())]);
+
+    let label = proc_label(
+        "parse",
+        Some("parse :: proc(s: string) -> (res: T, ok: bool)"),
+        None,
+        None,
+    );
+    assert_eq!(
+        code(&label),
+        "parse :: proc(s: string) -> (res: T, ok: bool)"
+    );
+    assert_eq!(filter_text(&label), "parse");
+}
+
+#[test]
+fn proc_labels_keep_calling_conventions_and_directives() {
+    let label = proc_label("puts", Some("proc \"c\" (s: cstring) -> i32"), None, None);
+    assert_eq!(code(&label), "puts :: proc \"c\" (s: cstring) -> i32");
+
+    let label = proc_label(
+        "dot",
+        Some("#force_inline proc \"contextless\" (a, b: Vec3) -> f32"),
+        None,
+        None,
+    );
+    assert_eq!(
+        code(&label),
+        "dot :: #force_inline proc \"contextless\" (a, b: Vec3) -> f32"
+    );
+    assert_eq!(filter_text(&label), "dot");
+}
+
+#[test]
+fn proc_labels_only_trust_details_with_the_proc_keyword() {
+    let label = proc_label("f", Some("(x: process) -> int"), None, None);
+    assert_eq!(code(&label), "f :: proc(x: process) -> int");
+
+    let label = proc_label("spawn", Some("process_info"), Some("(cmd: string)"), None);
+    assert_eq!(code(&label), "spawn :: proc(cmd: string)");
+
+    let label = proc_label("draw", Some("proc{draw_rect, draw_circle}"), None, None);
+    assert_eq!(code(&label), "draw :: proc{draw_rect, draw_circle}");
+}
+
+#[test]
+fn proc_labels_fall_back_to_label_details() {
+    let label = proc_label("add", None, Some("(x: int, y: int)"), Some("int"));
+    assert_eq!(code(&label), "add :: proc(x: int, y: int) -> int");
+
+    let label = proc_label("add", Some("int"), Some("(x: int)"), Some("-> int"));
+    assert_eq!(code(&label), "add :: proc(x: int) -> int");
+
+    let label = proc_label("reset", None, Some("()"), None);
+    assert_eq!(code(&label), "reset :: proc()");
+
+    let label = proc_label("mystery", None, None, None);
+    assert_eq!(code(&label), "mystery :: proc");
+    assert_eq!(filter_text(&label), "mystery");
+}
+
+#[test]
+fn proc_groups_render_their_members() {
+    let label = proc_label(
+        "to_string",
+        Some("proc{int_to_string, f32_to_string}"),
+        None,
+        None,
+    );
+    assert_eq!(
+        code(&label),
+        "to_string :: proc{int_to_string, f32_to_string}"
+    );
+    assert_eq!(filter_text(&label), "to_string");
+
+    let label = proc_label(
+        "foo",
+        Some("foo :: proc {\n\ta,\n\tb,\n\tc,\n}"),
+        None,
+        None,
+    );
+    assert_eq!(code(&label), "foo :: proc{a, b, c}");
+}
+
+#[test]
+fn long_proc_groups_collapse_to_a_member_count() {
+    let label = proc_label("foo", Some("proc{a, b, c, d, e}"), None, None);
+    assert_eq!(code(&label), "foo :: proc{a, b, +3}");
+
+    let label = proc_label("foo", Some("proc{a, b, c, d}"), None, None);
+    assert_eq!(code(&label), "foo :: proc{a, b, +2}");
+
+    assert_eq!(proc_group_signature(&["a", "b", "c"]), "proc{a, b, c}");
+    assert_eq!(proc_group_signature(&[]), "proc{}");
+}
+
+#[test]
+fn single_procs_are_not_mistaken_for_groups() {
+    assert_eq!(proc_group_members("proc(x: int) -> int"), None);
+    assert_eq!(proc_group_members("proc \"c\" ()"), None);
+    assert_eq!(proc_group_members("proc{a, b}"), Some(vec!["a", "b"]));
+}
+
+fn display_text(label: &Label) -> String {
+    label
+        .spans
+        .iter()
+        .map(|span| match span {
+            LabelSpan::Code(range) => label.code[range.clone()].to_string(),
+            LabelSpan::Literal { text, .. } => text.clone(),
+        })
+        .collect()
+}
+
+fn polymorphic_texts(label: &Label) -> Vec<&str> {
+    label
+        .spans
+        .iter()
+        .filter_map(|span| match span {
+            LabelSpan::Literal {
+                text,
+                highlight: Some(POLYMORPHIC_HIGHLIGHT),
+            } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parametric_structs_and_unions_keep_their_parameters() {
+    let label = record_label(
+        "Small_Array",
+        "struct",
+        Some("struct($N: int, $T: typeid) {data: [N]T, len: int}"),
+    );
+    assert_eq!(code(&label), "Small_Array :: struct($N: int, $T: typeid)");
+    assert_eq!(display_text(&label), label.code);
+    assert_eq!(filter_text(&label), "Small_Array");
+    assert_eq!(polymorphic_texts(&label), vec!["$N", "$T"]);
+
+    let label = record_label("Maybe_Of", "union", Some("union($T: typeid) {T}"));
+    assert_eq!(code(&label), "Maybe_Of :: union($T: typeid)");
+
+    let label = record_label("Vec3", "struct", Some("struct {x, y, z: f32}"));
+    assert_eq!(code(&label), "Vec3 :: struct");
+    assert_eq!(label.spans, vec![LabelSpan::Code(0..label.code.len())]);
+
+    let label = record_label("Broken", "struct", Some("struct($T: typeid"));
+    assert_eq!(code(&label), "Broken :: struct");
+    assert_eq!(
+        record_label("Plain", "struct", None).code,
+        "Plain :: struct"
+    );
+}
+
+#[test]
+fn generic_procs_highlight_polymorphic_parameters() {
+    let label = proc_label(
+        "make_slice",
+        Some("proc($T: typeid, n: int) -> []T"),
+        None,
+        None,
+    );
+    assert_eq!(
+        code(&label),
+        "make_slice :: proc($T: typeid, n: int) -> []T"
+    );
+    assert_eq!(display_text(&label), label.code);
+    assert_eq!(polymorphic_texts(&label), vec!["$T"]);
+    assert_eq!(
+        label.spans,
+        vec![
+            LabelSpan::Code(0..19),
+            LabelSpan::Literal {
+                text: "$T".to_string(),
+                highlight: Some(POLYMORPHIC_HIGHLIGHT),
+            },
+            LabelSpan::Code(21..label.code.len()),
+        ]
+    );
+
+    let label = proc_label("fmt", Some("proc(s: string = \"$x\", v: $E)"), None, None);
+    assert_eq!(polymorphic_texts(&label), vec!["$E"]);
+    assert!(label.code.ends_with("$E)"));
+}
+
+#[test]
+fn type_parameters_render_as_dollar_declarations() {
+    let label = type_parameter_label("T", Some("typeid"));
+    assert_eq!(display_text(&label), "$T: typeid");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "T");
+    assert_eq!(polymorphic_texts(&label), vec!["$T"]);
+
+    let label = type_parameter_label("$N", Some("int"));
+    assert_eq!(display_text(&label), "$N: int");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "N");
+
+    assert_eq!(display_text(&type_parameter_label("T", None)), "$T");
+}
+
+#[test]
+fn type_expressions_are_classified() {
+    use TypeExpr::*;
+    let cases = [
+        ("struct {x: f32}", Some(Struct)),
+        ("struct($T: typeid)", Some(Struct)),
+        ("union {int, f32}", Some(Union)),
+        ("enum u8 {A, B}", Some(Enum)),
+        ("bit_field u32 {a: u8 | 4}", Some(BitField)),
+        ("bit_set[Flag; u8]", Some(BitSet)),
+        ("matrix[4, 4]f32", Some(Matrix)),
+        ("[Direction]Vec2", Some(EnumeratedArray)),
+        ("#sparse[Key]int", Some(EnumeratedArray)),
+        ("[4]f32", Some(FixedArray)),
+        ("[]u8", Some(Slice)),
+        ("[dynamic]Entity", Some(DynamicArray)),
+        ("#soa[]Entity", Some(Soa)),
+        ("#soa[dynamic]Entity", Some(Soa)),
+        ("#simd[4]f32", Some(Simd)),
+        ("map[string]int", Some(Map)),
+        ("^Node", Some(Pointer)),
+        ("[^]u8", Some(MultiPointer)),
+        ("distinct int", Some(Distinct)),
+        ("proc(x: int) -> int", Some(Proc)),
+        ("#type proc \"c\" ()", Some(Proc)),
+        ("Vec3", None),
+        ("structure", None),
+        ("process_info", None),
+        ("proc_table", None),
+        ("enumerated", None),
+        ("", None),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(classify_type_expr(expr), expected, "{expr}");
+    }
+}
+
+#[test]
+fn type_labels_show_the_real_declaration() {
+    let cases = [
+        (
+            "Flags",
+            "bit_field u32 {a: u8 | 4, b: bool | 1}",
+            "Flags :: bit_field u32",
+        ),
+        ("Mat4", "matrix[4, 4]f32", "Mat4 :: matrix[4, 4]f32"),
+        ("Offsets", "[Direction]Vec2", "Offsets :: [Direction]Vec2"),
+        ("Entities", "#soa[]Entity", "Entities :: #soa[]Entity"),
+        ("Set", "bit_set[Flag; u8]", "Set :: bit_set[Flag; u8]"),
+        ("Id", "distinct u32", "Id :: distinct u32"),
+        ("Buffer", "[dynamic]u8", "Buffer :: [dynamic]u8"),
+        ("Table", "map[string]int", "Table :: map[string]int"),
+        (
+            "Callback",
+            "proc \"c\" (user: rawptr)",
+            "Callback :: proc \"c\" (user: rawptr)",
+        ),
+        ("Vec3", "struct {x, y, z: f32}", "Vec3 :: struct"),
+        ("Alias", "Vec3", "Alias :: struct"),
+    ];
+    for (name, detail, expected) in cases {
+        let label = type_label(name, Some(detail), "struct");
+        assert_eq!(code(&label), expected);
+        assert_eq!(filter_text(&label), name);
+    }
+    assert_eq!(type_label("Empty", None, "struct").code, "Empty :: struct");
+}
+
+#[test]
+fn enum_and_union_type_labels() {
+    let label = type_label("Value", Some("union { int, f32 }"), "enum");
+    assert_eq!(code(&label), "Value :: union");
+    let label = type_label("Dir", Some("u8"), "enum");
+    assert_eq!(code(&label), "Dir :: enum u8");
+    let label = type_label("Dir", Some("enum u8 {North, South}"), "enum");
+    assert_eq!(code(&label), "Dir :: enum u8");
+    let label = type_label("Dir", Some("enum {North, South}"), "enum");
+    assert_eq!(code(&label), "Dir :: enum");
+    assert_eq!(type_label("Dir", None, "enum").code, "Dir :: enum");
+}
+
+#[test]
+fn collection_imports_are_found_in_detail_text() {
+    assert_eq!(collection_import("core:strings"), Some(("core", "strings")));
+    assert_eq!(
+        collection_import("import \"vendor:raylib\""),
+        Some(("vendor", "raylib"))
+    );
+    assert_eq!(
+        collection_import("strings (core:text/regex)"),
+        Some(("core", "text/regex"))
+    );
+    assert_eq!(
+        collection_import("import ecs \"project:libs/ecs\""),
+        Some(("project", "libs/ecs"))
+    );
+    assert_eq!(collection_import("C:\\odin\\core\\strings"), None);
+    assert_eq!(collection_import("/usr/lib/odin/core/strings"), None);
+    assert_eq!(collection_import("strings"), None);
+    assert_eq!(collection_import("core:"), None);
+}
+
+#[test]
+fn import_labels_highlight_the_collection_and_filter_on_the_package() {
+    let label = import_label("strings", &[Some("core:strings")]);
+    assert_eq!(display_text(&label), "import \"core:strings\"");
+    assert_eq!(label.code, display_text(&label));
+    assert_eq!(&label.code[label.filter_range.clone()], "strings");
+    assert_eq!(
+        label.spans[1],
+        LabelSpan::Literal {
+            text: "core".to_string(),
+            highlight: Some(COLLECTION_HIGHLIGHT),
+        }
+    );
+
+    let label = import_label("regex", &[None, Some("core:text/regex")]);
+    assert_eq!(display_text(&label), "import \"core:text/regex\"");
+    assert_eq!(&label.code[label.filter_range.clone()], "regex");
+
+    let label = import_label("raylib", &[Some("vendor:raylib")]);
+    assert_eq!(&label.code[label.filter_range.clone()], "raylib");
+}
+
+#[test]
+fn import_labels_fall_back_to_the_package_name() {
+    let label = import_label("strings", &[Some("/opt/odin/core/strings"), None]);
+    assert_eq!(label.code, "package strings");
+    assert_eq!(display_text(&label), "strings");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "strings");
+}
+
+#[test]
+fn qualified_symbol_names_split_into_container_and_member() {
+    assert_eq!(split_container("Vec3.x"), (Some("Vec3"), "x"));
+    assert_eq!(
+        split_container("renderer.Mesh.draw"),
+        (Some("renderer.Mesh"), "draw")
+    );
+    assert_eq!(split_container("main"), (None, "main"));
+    assert_eq!(split_container(".North"), (None, ".North"));
+    assert_eq!(split_container("trailing."), (None, "trailing."));
+}
+
+#[test]
+fn containers_prefix_the_label_and_join_the_filter() {
+    let label = with_container(variable_label("x", Some("f32")), Some("Vec3"), "type");
+    assert_eq!(display_text(&label), "Vec3.x: f32");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "Vec3.x");
+    assert_eq!(
+        label.spans[0],
+        LabelSpan::Literal {
+            text: "Vec3".to_string(),
+            highlight: Some("type"),
+        }
+    );
+
+    let label = with_container(
+        proc_label("draw", Some("proc(m: ^Mesh)"), None, None),
+        Some("renderer"),
+        COLLECTION_HIGHLIGHT,
+    );
+    assert_eq!(display_text(&label), "renderer.draw :: proc(m: ^Mesh)");
+    assert_eq!(
+        &display_text(&label)[label.filter_range.clone()],
+        "renderer.draw"
+    );
+
+    let label = with_container(
+        variable_label("x", Some("using Base")),
+        Some("Vec3"),
+        "type",
+    );
+    assert_eq!(display_text(&label), "using Vec3.x: Base");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "Vec3.x");
+
+    let label = with_container(variable_label("count", None), None, "type");
+    assert_eq!(display_text(&label), "count");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "count");
+}
+
+#[test]
+fn symbols_without_types_never_show_a_placeholder() {
+    assert_eq!(display_text(&variable_label("x", None)), "x");
+    assert_eq!(display_text(&variable_label("x", Some("  "))), "x");
+    assert_eq!(
+        display_text(&variable_label("x", Some("[]int"))),
+        "x: []int"
+    );
+
+    let label = package_label("strings");
+    assert_eq!(display_text(&label), "strings");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "strings");
+
+    let label = type_name_label("Allocator");
+    assert_eq!(display_text(&label), "Allocator");
+    assert_eq!(label.code, "Allocator :: struct");
+}
+
+#[test]
+fn variable_types_are_normalized_for_the_grammar() {
+    assert_eq!(format_type_expr(" ^ Node "), "^Node");
+    assert_eq!(format_type_expr("[ ^ ]u8"), "[^]u8");
+    assert_eq!(format_type_expr("[] ^ Node"), "[]^Node");
+    assert_eq!(format_type_expr("[ dynamic ] [4]f32"), "[dynamic][4]f32");
+    assert_eq!(
+        format_type_expr("map[ [2]int ] [dynamic] ^ Entity"),
+        "map[[2]int][dynamic]^Entity"
+    );
+    assert_eq!(format_type_expr("proc(x: int)"), "proc(x: int)");
+
+    let label = variable_label("nodes", Some("[dynamic] ^ Node"));
+    assert_eq!(code(&label), "nodes: [dynamic]^Node");
+    assert_eq!(filter_text(&label), "nodes");
+    assert_eq!(label.spans, vec![LabelSpan::Code(0..label.code.len())]);
+}
+
+#[test]
+fn variable_details_that_repeat_the_name_show_only_the_type() {
+    assert_eq!(code(&variable_label("pos", Some("pos: Vec3"))), "pos: Vec3");
+    assert_eq!(
+        code(&variable_label("data", Some("data : [^]u8"))),
+        "data: [^]u8"
+    );
+    assert_eq!(
+        code(&variable_label("posx", Some("pos: Vec3"))),
+        "posx: pos: Vec3"
+    );
+}
+
+#[test]
+fn using_fields_are_marked_but_filter_on_their_name() {
+    let label = variable_label("base", Some("using base: ^Entity"));
+    assert_eq!(code(&label), "using base: ^Entity");
+    assert_eq!(filter_text(&label), "base");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "base");
+
+    let label = variable_label("base", Some("using "));
+    assert_eq!(code(&label), "using base");
+    assert_eq!(filter_text(&label), "base");
+}
+
+#[test]
+fn unions_reported_as_enums_are_told_apart_by_detail() {
+    assert!(is_union_detail("union { int, f32 }"));
+    assert!(is_union_detail("Value :: union #no_nil {int, f32}"));
+    assert!(is_union_detail("#maybe union {^Node}"));
+    assert!(!is_union_detail("enum u8 {A, B}"));
+    assert!(!is_union_detail("u8"));
+    assert!(!is_union_detail("Union_Kind"));
+    assert!(!is_union_detail("enum {Union, Struct}"));
+
+    let cases = [
+        (Some("union { int, f32 }"), "Value :: union"),
+        (
+            Some("union($T: typeid) {T, Error}"),
+            "Value :: union($T: typeid)",
+        ),
+        (Some("#maybe union {^Node}"), "Value :: union"),
+        (Some("enum u8 {A, B}"), "Value :: enum u8"),
+        (Some("i32"), "Value :: enum i32"),
+        (Some("enum {Union, Struct}"), "Value :: enum"),
+        (None, "Value :: enum"),
+    ];
+    for (detail, expected) in cases {
+        let label = enum_or_union_label("Value", detail);
+        assert_eq!(code(&label), expected, "{detail:?}");
+        assert_eq!(filter_text(&label), "Value");
+    }
+}
+
+#[test]
+fn directive_and_attribute_keywords_get_their_own_highlight() {
+    let highlight = |label: Label| match &label.spans[..] {
+        [LabelSpan::Literal { highlight, .. }] => *highlight,
+        spans => panic!("unexpected spans {spans:?}"),
+    };
+    assert_eq!(
+        highlight(keyword_label("#partial")),
+        Some(DIRECTIVE_HIGHLIGHT)
+    );
+    assert_eq!(
+        highlight(keyword_label("#no_bounds_check")),
+        Some(DIRECTIVE_HIGHLIGHT)
+    );
+    assert_eq!(
+        highlight(keyword_label("@(require_results)")),
+        Some(ATTRIBUTE_HIGHLIGHT)
+    );
+    assert_eq!(highlight(keyword_label("defer")), Some("keyword"));
+
+    let label = keyword_label("#partial");
+    assert_eq!(display_text(&label), "#partial");
+    assert_eq!(label.filter_range, 0..8);
+}
+
+#[test]
+fn snippets_expand_placeholders_and_keep_literal_braces() {
+    assert_eq!(
+        expand_snippet("fmt.println(${1:args})$0"),
+        "fmt.println(args)"
+    );
+    assert_eq!(
+        expand_snippet("fmt.printf(\"${1}\", $2)"),
+        "fmt.printf(\"\", )"
+    );
+    assert_eq!(
+        expand_snippet("main :: proc() {\n\t$0\n}"),
+        "main :: proc() { }"
+    );
+    assert_eq!(
+        expand_snippet("for ${1:i} in ${2:0..<n} {\n\t$0\n}"),
+        "for i in 0..<n { }"
+    );
+    assert_eq!(expand_snippet("cost: \\$${1:5}"), "cost: $5");
+}
+
+#[test]
+fn snippet_labels_preview_the_expansion() {
+    let label = snippet_label("fmt.println", Some("fmt.println(${1:args})"));
+    assert_eq!(display_text(&label), "fmt.println(args)");
+    assert_eq!(
+        &display_text(&label)[label.filter_range.clone()],
+        "fmt.println"
+    );
+
+    let label = snippet_label("forr", Some("for ${1:i} in ${2:0..<n} {\n\t$0\n}"));
+    assert_eq!(display_text(&label), "forr  for i in 0..<n { }");
+    assert_eq!(&display_text(&label)[label.filter_range.clone()], "forr");
+    assert_eq!(label.code, "for i in 0..<n { }");
+
+    for snippet in [None, Some(""), Some("todo")] {
+        let label = snippet_label("todo", snippet);
+        assert_eq!(display_text(&label), "todo");
+        assert_eq!(label.filter_range, 0..4);
+    }
+}
+
+fn is_balanced(text: &str) -> bool {
+    let mut stack = Vec::new();
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '`' | '\'') => quote = Some(c),
+            (None, '(' | '[' | '{') => stack.push(c),
+            (None, ')') if stack.pop() != Some('(') => return false,
+            (None, ']') if stack.pop() != Some('[') => return false,
+            (None, '}') if stack.pop() != Some('{') => return false,
+            _ => {}
+        }
+    }
+    stack.is_empty() && quote.is_none()
+}
+
+fn assert_well_formed(label: &Label) {
+    for span in &label.spans {
+        if let LabelSpan::Code(range) = span {
+            assert!(range.start <= range.end, "{label:?}");
+            assert!(range.end <= label.code.len(), "{label:?}");
+            assert!(label.code.is_char_boundary(range.start), "{label:?}");
+            assert!(label.code.is_char_boundary(range.end), "{label:?}");
+        }
+    }
+    let display = display_text(label);
+    assert!(label.filter_range.end <= display.len(), "{label:?}");
+    assert!(
+        display.is_char_boundary(label.filter_range.start),
+        "{label:?}"
+    );
+    assert!(
+        display.is_char_boundary(label.filter_range.end),
+        "{label:?}"
+    );
+}
+
+#[test]
+fn long_parameter_lists_lose_their_middle_first() {
+    let params: Vec<String> = (0..12).map(|i| format!("param_{i}: int")).collect();
+    let detail = format!("proc({}) -> bool", params.join(", "));
+    let label = proc_label("configure_everything", Some(&detail), None, None);
+    assert_eq!(
+        label.code,
+        "configure_everything :: proc(param_0: int, …, param_11: int) -> bool"
+    );
+    assert_eq!(filter_text(&label), "configure_everything");
+    assert_well_formed(&label);
+}
+
+#[test]
+fn nested_groups_collapse_when_eliding_the_middle_is_not_enough() {
+    let fields: Vec<String> = (0..8)
+        .map(|i| format!("f{i}: map[string][dynamic]Entity"))
+        .collect();
+    let detail = format!(
+        "proc(cb: proc(a: {}, b: int) -> int, user: rawptr)",
+        fields.join(", ")
+    );
+    let label = proc_label("register", Some(&detail), None, None);
+    assert!(
+        label.code.chars().count() <= MAX_LABEL_CHARS,
+        "{}",
+        label.code
+    );
+    assert!(label.code.starts_with("register :: proc("));
+    assert!(label.code.contains('…'));
+    assert!(is_balanced(&label.code), "{}", label.code);
+    assert_well_formed(&label);
+}
+
+#[test]
+fn short_labels_are_left_alone() {
+    let code = "lerp :: proc(a, b: f32, t: f32) -> f32".to_string();
+    assert_eq!(shorten(code.clone(), 4, MAX_LABEL_CHARS), code);
+}
+
+#[test]
+fn labels_without_brackets_are_cut_after_the_name() {
+    let shortened = shorten(format!("name :: {}", "x".repeat(200)), 4, 20);
+    assert_eq!(shortened.chars().count(), 20);
+    assert!(shortened.starts_with("name :: "));
+    assert!(shortened.ends_with('…'));
+
+    let long_name = "n".repeat(100);
+    let shortened = shorten(format!("{long_name} :: struct"), long_name.len(), 20);
+    assert!(shortened.starts_with(&long_name));
+
+    let shortened = shorten(format!("name :: proc \"{}\" ()", "c".repeat(200)), 4, 20);
+    assert!(shortened.ends_with("…\""), "{shortened}");
+    assert!(is_balanced(&shortened), "{shortened}");
+}
+
+/// A small deterministic generator so the property checks below explore many
+/// shapes without pulling in a property-testing crate.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+
+    fn type_expr(&mut self, depth: usize) -> String {
+        let leaves = ["int", "f32", "string", "Vec3", "Ünïcode_Τype", "rawptr"];
+        if depth == 0 {
+            return leaves[self.next(leaves.len())].to_string();
+        }
+        match self.next(8) {
+            0 => format!("[]{}", self.type_expr(depth - 1)),
+            1 => format!(
+                "map[{}]{}",
+                self.type_expr(depth - 1),
+                self.type_expr(depth - 1)
+            ),
+            2 => format!("^{}", self.type_expr(depth - 1)),
+            3 => format!(
+                "proc({}) -> {}",
+                self.params(depth - 1),
+                self.type_expr(depth - 1)
+            ),
+            4 => format!(
+                "struct($T: typeid, $N: int) {{ a: {} }}",
+                self.type_expr(depth - 1)
+            ),
+            5 => format!("#soa[dynamic]{}", self.type_expr(depth - 1)),
+            6 => "proc \"c\" (s: cstring)".to_string(),
+            _ => leaves[self.next(leaves.len())].to_string(),
+        }
+    }
+
+    fn params(&mut self, depth: usize) -> String {
+        let count = self.next(10);
+        (0..count)
+            .map(|i| format!("p{i}: {}", self.type_expr(depth)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+#[test]
+fn shortened_labels_never_point_past_their_code() {
+    let mut rng = Lcg(0x0d1_2026);
+    // `using` details draw from their own generator so the other labels keep
+    // seeing the same sequence.
+    let mut using_rng = Lcg(0x05e_2026);
+    for _ in 0..2000 {
+        let name = [
+            "x",
+            "make_slice",
+            "Ω_thing",
+            "a_really_long_procedure_name_for_testing",
+        ][rng.next(4)];
+        let detail = format!("proc({}) -> ({})", rng.params(3), rng.params(1));
+        // Every generated type expression is balanced, so every label built
+        // from one must be too. Snippets are only checked for offsets: the
+        // generator does not escape `}` inside placeholders.
+        let labels = [
+            proc_label(name, Some(&detail), None, None),
+            type_label(name, Some(&rng.type_expr(4)), "struct"),
+            enum_or_union_label(name, Some(&format!("union {{ {} }}", rng.params(2)))),
+            variable_label(name, Some(&rng.type_expr(4))),
+            with_container(
+                variable_label(name, Some(&rng.type_expr(4))),
+                Some("Outer"),
+                "type",
+            ),
+            with_container(
+                variable_label(name, Some(&format!("using {}", using_rng.type_expr(4)))),
+                Some("Outer"),
+                "type",
+            ),
+            type_parameter_label(name, Some(&rng.type_expr(4))),
+        ];
+        for label in &labels {
+            assert_well_formed(label);
+            assert!(is_balanced(&label.code), "{}", label.code);
+        }
+        assert_well_formed(&snippet_label(
+            name,
+            Some(&format!("{name}(${{1:{}}})", rng.params(2))),
+        ));
+        assert!(
+            labels[0].code.chars().count() <= MAX_LABEL_CHARS + 4,
+            "{}",
+            labels[0].code
+        );
+        assert_eq!(filter_text(&labels[0]), name);
+        for label in &labels[4..6] {
+            let display = display_text(label);
+            assert_eq!(
+                &display[label.filter_range.clone()],
+                format!("Outer.{name}")
+            );
+        }
+    }
+}
@@ -13,6 +13,10 @@ fn record(age_secs: u64, version: &str) -> String {
     format_check_record(NOW - age_secs, version)
 }
 
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
 #[test]
 fn check_version_returned_within_interval() {
     assert_eq!(
@@ -123,6 +127,16 @@ fn strip_extension_settings_removes_only_release_tag() {
     let mut settings = serde_json::json!({
         "release_tag": "nightly",
         "lldb_script": ".zed/my_odin.py",
+        "auto_collections": true,
+        "debug_output_dir": ".zed/odin-debug",
+        "debug_downgrade_optimization": true,
+        "debug_wrapper_commands": ["./build.sh"],
+        "debug_host_program": "bin/host",
+        "debug_host_args": ["--hot-reload"],
+        "debug_attach_program": "game",
+        "debug_attach_wait_for": true,
+        "debug_break_on_panic": false,
+        "debug_test_track_memory": true,
         "odin_command": "/usr/local/bin/odin",
     });
     strip_extension_settings(&mut settings);
@@ -145,9 +159,74 @@ fn strip_extension_settings_ignores_non_objects() {
     }
 }
 
+#[test]
+fn platform_overrides_merge_the_current_os_and_drop_the_block() {
+    let mut settings = serde_json::json!({
+        "lldb_script": ".zed/odin.py",
+        "checker_args": "-vet",
+        "collections": [{ "name": "shared", "path": "/home/me/shared" }],
+        "binary": { "arguments": ["--verbose"] },
+        "platform": {
+            "windows": {
+                "lldb_script": ".zed\\odin_win.py",
+                "collections": [{ "name": "shared", "path": "C:\\shared" }],
+                "binary": { "path": "C:\\tools\\ols.exe" },
+            },
+            "mac": { "checker_args": "-vet -strict-style" },
+        },
+    });
+    apply_platform_overrides(&mut settings, "windows");
+    assert_eq!(
+        settings,
+        serde_json::json!({
+            "lldb_script": ".zed\\odin_win.py",
+            "checker_args": "-vet",
+            "collections": [{ "name": "shared", "path": "C:\\shared" }],
+            "binary": { "arguments": ["--verbose"], "path": "C:\\tools\\ols.exe" },
+        })
+    );
+}
+
+#[test]
+fn platform_overrides_for_other_oses_are_discarded() {
+    let mut settings = serde_json::json!({
+        "release_tag": "nightly",
+        "platform": { "windows": { "release_tag": "dev-2026-06" } },
+    });
+    apply_platform_overrides(&mut settings, "linux");
+    assert_eq!(settings, serde_json::json!({ "release_tag": "nightly" }));
+
+    for mut untouched in [
+        serde_json::json!({ "release_tag": "nightly" }),
+        serde_json::Value::Null,
+        serde_json::json!(["platform"]),
+    ] {
+        let before = untouched.clone();
+        apply_platform_overrides(&mut untouched, "mac");
+        assert_eq!(untouched, before);
+    }
+
+    let mut malformed = serde_json::json!({ "a": 1, "platform": { "mac": "oops" } });
+    apply_platform_overrides(&mut malformed, "mac");
+    assert_eq!(malformed, serde_json::json!({ "a": 1 }));
+}
+
+#[test]
+fn deep_merge_replaces_scalars_and_arrays() {
+    let mut base = serde_json::json!({ "a": { "b": 1, "c": [1, 2] }, "d": null });
+    deep_merge(
+        &mut base,
+        serde_json::json!({ "a": { "c": [3] }, "d": { "e": true } }),
+    );
+    assert_eq!(
+        base,
+        serde_json::json!({ "a": { "b": 1, "c": [3] }, "d": { "e": true } })
+    );
+}
+
 #[test]
 fn initialization_defaults_never_override_user_options() {
-    let defaults = merged_initialization_options(None);
+    let (defaults, _) = merged_initialization_options(None, None);
     assert_eq!(
         defaults,
         serde_json::json!({
@@ -165,15 +244,280 @@ fn initialization_defaults_never_override_user_options() {
         "enable_hover": false,
         "collections": [{"name": "shared", "path": "/x"}],
     });
-    let merged = merged_initialization_options(Some(user));
+    let (merged, _) = merged_initialization_options(Some(user), None);
     assert_eq!(merged["enable_hover"], false);
     assert_eq!(merged["enable_snippets"], true);
     assert_eq!(merged["collections"][0]["name"], "shared");
 
-    let passthrough = merged_initialization_options(Some(serde_json::json!(null)));
+    let (passthrough, _) = merged_initialization_options(Some(serde_json::json!(null)), None);
     assert_eq!(passthrough, serde_json::Value::Null);
 }
 
+#[test]
+fn initialization_defaults_grow_with_the_resolved_release() {
+    let (old, _) = merged_initialization_options(None, Some("dev-2024-11"));
+    assert_eq!(old, merged_initialization_options(None, None).0);
+
+    let (newer, _) = merged_initialization_options(None, Some("dev-2025-04"));
+    assert_eq!(newer["enable_document_highlights"], true);
+    assert!(newer.get("enable_inlay_hints_implicit_return").is_none());
+
+    for release in ["dev-2026-06", NIGHTLY_TAG] {
+        let (merged, _) = merged_initialization_options(None, Some(release));
+        for key in BASE_DEFAULT_OPTIONS.iter().chain(
+            VERSIONED_DEFAULT_OPTIONS
+                .iter()
+                .flat_map(|(_, keys)| keys.iter()),
+        ) {
+            assert_eq!(merged[key], true, "{key} missing for {release}");
+        }
+    }
+
+    let user = serde_json::json!({ "enable_document_highlights": false });
+    let (merged, _) = merged_initialization_options(Some(user), Some("dev-2026-06"));
+    assert_eq!(merged["enable_document_highlights"], false);
+}
+
+#[test]
+fn deprecated_option_keys_are_migrated_with_one_notice_each() {
+    let user = serde_json::json!({
+        "enable_inlay_hints": false,
+        "checker_arguments": "-vet",
+        "enable_format": true,
+    });
+    let (merged, notices) = merged_initialization_options(Some(user), None);
+    assert_eq!(merged["enable_inlay_hints_params"], false);
+    assert_eq!(merged["checker_args"], "-vet");
+    assert_eq!(merged["enable_format"], true);
+    assert!(merged.get("enable_inlay_hints").is_none());
+    assert!(merged.get("checker_arguments").is_none());
+    assert_eq!(notices.len(), 2);
+    assert!(notices.iter().all(|notice| !notice.contains('\n')));
+    assert!(notices[0].contains("`enable_inlay_hints`"));
+    assert!(notices[0].contains("`enable_inlay_hints_params`"));
+
+    let (_, notices) = merged_initialization_options(Some(serde_json::json!({})), None);
+    assert!(notices.is_empty());
+}
+
+#[test]
+fn deprecated_option_keys_never_overwrite_the_current_name() {
+    let user = serde_json::json!({
+        "enable_inlay_hints": true,
+        "enable_inlay_hints_params": false,
+    });
+    let (merged, notices) = merged_initialization_options(Some(user), None);
+    assert_eq!(merged["enable_inlay_hints_params"], false);
+    assert_eq!(merged["enable_inlay_hints_default_params"], true);
+    assert!(merged.get("enable_inlay_hints").is_none());
+    assert_eq!(notices.len(), 1);
+    assert!(notices[0].contains("`enable_inlay_hints_params` is also set and wins"));
+
+    let user = serde_json::json!({
+        "checker_arguments": "-vet",
+        "checker_args": "-strict-style",
+    });
+    let (merged, notices) = merged_initialization_options(Some(user), None);
+    assert_eq!(merged["checker_args"], "-strict-style");
+    assert!(notices[0].contains("ignored"));
+}
+
+#[test]
+fn split_option_keys_turn_off_every_replacement() {
+    // `enable_inlay_hints` was split, so `false` must also beat the defaults
+    // for default-param and implicit-return hints.
+    let user = serde_json::json!({ "enable_inlay_hints": false });
+    let (merged, notices) = merged_initialization_options(Some(user), Some("dev-2025-07"));
+    assert_eq!(merged["enable_inlay_hints_params"], false);
+    assert_eq!(merged["enable_inlay_hints_default_params"], false);
+    assert_eq!(merged["enable_inlay_hints_implicit_return"], false);
+    assert!(merged.get("enable_inlay_hints").is_none());
+    assert_eq!(
+        notices,
+        vec![
+            "OLS option `enable_inlay_hints` is deprecated, using it as \
+             `enable_inlay_hints_params`, `enable_inlay_hints_default_params`, \
+             `enable_inlay_hints_implicit_return`"
+        ]
+    );
+
+    let user = serde_json::json!({
+        "enable_inlay_hints": false,
+        "enable_inlay_hints_implicit_return": true,
+    });
+    let (merged, _) = merged_initialization_options(Some(user), Some("dev-2025-07"));
+    assert_eq!(merged["enable_inlay_hints_params"], false);
+    assert_eq!(merged["enable_inlay_hints_implicit_return"], true);
+
+    // Releases that predate a replacement key never receive it.
+    let user = serde_json::json!({ "enable_inlay_hints": false });
+    let (merged, _) = merged_initialization_options(Some(user), Some("dev-2025-04"));
+    assert_eq!(merged["enable_inlay_hints_default_params"], false);
+    assert!(merged.get("enable_inlay_hints_implicit_return").is_none());
+}
+
+#[test]
+fn release_ordering_is_by_date_with_nightly_newest() {
+    assert!(release_at_least(Some("dev-2025-04"), "dev-2025-04"));
+    assert!(release_at_least(Some("dev-2025-10"), "dev-2025-04"));
+    assert!(release_at_least(Some("dev-2026-01"), "dev-2025-12"));
+    assert!(release_at_least(Some("dev-2025-04-15"), "dev-2025-04"));
+    assert!(!release_at_least(Some("dev-2025-03"), "dev-2025-04"));
+    assert!(release_at_least(Some(NIGHTLY_TAG), "dev-2999-01"));
+    assert!(!release_at_least(Some("v1.0"), "dev-2025-04"));
+    assert!(!release_at_least(None, "dev-2025-04"));
+
+    assert!(versioned_defaults_for(None).is_empty());
+    assert_eq!(
+        versioned_defaults_for(Some("dev-2025-05")),
+        vec!["enable_document_highlights"]
+    );
+}
+
+#[test]
+fn installed_release_is_read_from_the_download_dir() {
+    assert_eq!(
+        installed_release(&format!("ols-dev-2026-06/{EXE}"), "/"),
+        Some("dev-2026-06")
+    );
+    assert_eq!(
+        installed_release("ols-nightly\\ols-x86_64-pc-windows-msvc.exe", "\\"),
+        Some("nightly")
+    );
+    assert_eq!(installed_release("/usr/local/bin/ols", "/"), None);
+    assert_eq!(installed_release("ols", "/"), None);
+    assert_eq!(installed_release("ols-/ols", "/"), None);
+}
+
+#[test]
+fn auto_collections_setting_is_opt_in() {
+    assert!(!auto_collections_from_settings(None));
+    assert!(!auto_collections_from_settings(Some(
+        &serde_json::json!({})
+    )));
+    assert!(!auto_collections_from_settings(Some(
+        &serde_json::json!({ "auto_collections": "yes" })
+    )));
+    assert!(auto_collections_from_settings(Some(
+        &serde_json::json!({ "auto_collections": true })
+    )));
+}
+
+#[test]
+fn collection_imports_are_parsed_with_and_without_aliases() {
+    let source = r#"package main
+
+import "core:fmt"
+import rl "vendor:raylib"
+import "libs:ecs"
+  import game "src:game/world"
+import "relative/pkg"
+important := "x:y"
+// import "commented:out"
+"#;
+    assert_eq!(
+        collection_imports(source),
+        vec![
+            ("core".to_string(), "fmt".to_string()),
+            ("vendor".to_string(), "raylib".to_string()),
+            ("libs".to_string(), "ecs".to_string()),
+            ("src".to_string(), "game/world".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn configured_collections_and_gitmodules_are_read() {
+    let options = serde_json::json!({
+        "collections": [{ "name": "shared_lib", "path": "/x" }, { "path": "/nameless" }],
+    });
+    assert_eq!(configured_collections(&options), vec!["shared_lib"]);
+    assert!(configured_collections(&serde_json::json!({})).is_empty());
+
+    let gitmodules = r#"[submodule "libs/ecs"]
+	path = libs/ecs
+	url = https://example.com/ecs.git
+[submodule "libs/jolt"]
+	path=libs/jolt
+[submodule "tools"]
+	path = tools
+"#;
+    assert_eq!(gitmodule_top_dirs(gitmodules), vec!["libs", "tools"]);
+}
+
+#[test]
+fn auto_collections_only_propose_unknown_top_level_dirs() {
+    let imports = collection_imports(
+        "import \"core:fmt\"\nimport \"libs:ecs\"\nimport \"game:world\"\nimport \"shared_lib:x\"\nimport \"libs:jolt\"\n",
+    );
+
+    let derived = auto_collections(
+        &imports,
+        &args(&["src", "libs", "tools"]),
+        &args(&["shared_lib"]),
+    );
+    assert_eq!(
+        derived,
+        AutoCollections {
+            found: args(&["src", "libs"]),
+            unresolved: args(&["game"]),
+        }
+    );
+
+    let derived = auto_collections(&imports, &args(&["game"]), &args(&["libs"]));
+    assert_eq!(derived.found, args(&["game"]));
+    assert_eq!(derived.unresolved, args(&["shared_lib"]));
+}
+
+#[test]
+fn top_dirs_are_confirmed_by_the_files_inside_them() {
+    let imports = collection_imports(
+        "import \"core:fmt\"\nimport \"libs:raylib\"\nimport \"game:world/map\"\nimport \"tools:gen\"\n",
+    );
+    let files = [
+        "src/main.odin",
+        "libs/raylib/raylib.odin",
+        "game/world/map/map.odin",
+    ];
+    let dirs = probe_top_dirs(&imports, |path| files.contains(&path));
+    assert_eq!(dirs, vec!["src", "libs", "game"]);
+
+    // A vendored `libs/` that is not a submodule is still found, and leads
+    // to a collection without any `.gitmodules` entry.
+    let derived = auto_collections(&imports, &dirs, &[]);
+    assert!(derived.found.contains(&"libs".to_string()));
+    assert_eq!(derived.unresolved, vec!["tools".to_string()]);
+
+    assert_eq!(
+        probe_top_dirs(&[], |path| path == "libs/libs.odin"),
+        vec!["libs"]
+    );
+    assert!(probe_top_dirs(&imports, |_| false).is_empty());
+}
+
+#[test]
+fn inject_collections_appends_to_existing_entries() {
+    let mut options = serde_json::json!({
+        "collections": [{ "name": "shared_lib", "path": "/x" }],
+    });
+    inject_collections(&mut options, &["src".to_string()], "/proj", "/");
+    assert_eq!(
+        options["collections"],
+        serde_json::json!([
+            { "name": "shared_lib", "path": "/x" },
+            { "name": "src", "path": "/proj/src" },
+        ])
+    );
+
+    let mut options = serde_json::json!({});
+    inject_collections(&mut options, &["libs".to_string()], "C:\\proj", "\\");
+    assert_eq!(options["collections"][0]["path"], "C:\\proj\\libs");
+
+    let mut options = serde_json::Value::Null;
+    inject_collections(&mut options, &["src".to_string()], "/proj", "/");
+    assert_eq!(options, serde_json::Value::Null);
+}
+
 #[test]
 fn debug_output_names_are_derived_from_the_resolved_label() {
     assert_eq!(
@@ -189,6 +533,10 @@ fn debug_output_names_are_derived_from_the_resolved_label() {
         "debug_build-my_test"
     );
     assert_eq!(debug_output_name("test: 'src'", ""), "debug_build-src");
+    assert_eq!(
+        debug_output_name("build: package 'src' (debug)", ""),
+        "debug_build-package_src_debug"
+    );
     assert_eq!(
         debug_output_name("run: 'main.odin'", ".exe"),
         "debug_build-main_odin.exe"
@@ -590,14 +938,16 @@ fn download_that_yields_no_binary_is_rejected() {
 
 #[test]
 fn release_without_matching_asset_errors() {
-    let mut host = FakeHost::default();
-    host.release = Some(Release {
-        version: "dev-2026-06".to_string(),
-        assets: vec![ReleaseAsset {
-            name: "ols-source-only.tar.gz".to_string(),
-            download_url: "https://example.com/src.tar.gz".to_string(),
-        }],
-    });
+    let mut host = FakeHost {
+        release: Some(Release {
+            version: "dev-2026-06".to_string(),
+            assets: vec![ReleaseAsset {
+                name: "ols-source-only.tar.gz".to_string(),
+                download_url: "https://example.com/src.tar.gz".to_string(),
+            }],
+        }),
+        ..FakeHost::default()
+    };
 
     let err = resolve_ols_binary(&mut host, &inputs(None, None)).unwrap_err();
     assert!(err.contains("no asset found"));
@@ -649,14 +999,16 @@ fn pin_unpin_replay_never_serves_the_pinned_version() {
 #[test]
 fn windows_inputs_produce_backslash_paths_with_exe_suffix() {
     let stem = "ols-x86_64-pc-windows-msvc";
-    let mut host = FakeHost::default();
-    host.release = Some(Release {
-        version: "dev-2026-06".to_string(),
-        assets: vec![ReleaseAsset {
-            name: format!("{stem}.zip"),
-            download_url: format!("https://example.com/{stem}.zip"),
-        }],
-    });
+    let mut host = FakeHost {
+        release: Some(Release {
+            version: "dev-2026-06".to_string(),
+            assets: vec![ReleaseAsset {
+                name: format!("{stem}.zip"),
+                download_url: format!("https://example.com/{stem}.zip"),
+            }],
+        }),
+        ..FakeHost::default()
+    };
     let req = ResolveInputs {
         cached_binary_path: None,
         release_tag: None,
@@ -689,3 +1041,600 @@ fn nightly_roundtrip_respects_the_setting_at_every_step() {
     assert_eq!(resolved, format!("ols-dev-2026-06/{EXE}"));
     assert_eq!(host.list_ols_dirs(), vec!["ols-dev-2026-06".to_string()]);
 }
+
+#[test]
+fn program_args_are_split_from_compiler_args_at_the_first_separator() {
+    assert_eq!(
+        split_program_args(&args(&["run", ".", "-vet", "--", "--port", "8080", "--"])),
+        (args(&["run", ".", "-vet"]), args(&["--port", "8080", "--"]))
+    );
+    assert_eq!(
+        split_program_args(&args(&["run", "."])),
+        (args(&["run", "."]), vec![])
+    );
+    assert_eq!(
+        split_program_args(&args(&["run", ".", "--"])),
+        (args(&["run", "."]), vec![])
+    );
+}
+
+#[test]
+fn program_args_round_trip_through_the_build_environment() {
+    let program_args = vec!["--name".to_string(), "a b \"c\"".to_string()];
+    let mut env = vec![
+        ("ODIN_ROOT".to_string(), "/opt/odin".to_string()),
+        (
+            PROGRAM_ARGS_ENV.to_string(),
+            encode_program_args(&program_args),
+        ),
+    ];
+
+    assert_eq!(take_program_args(&mut env), program_args);
+    assert_eq!(
+        env,
+        vec![("ODIN_ROOT".to_string(), "/opt/odin".to_string())]
+    );
+
+    let mut env = vec![(PROGRAM_ARGS_ENV.to_string(), "not json".to_string())];
+    assert!(take_program_args(&mut env).is_empty());
+    assert!(env.is_empty());
+    assert!(take_program_args(&mut Vec::new()).is_empty());
+}
+
+#[test]
+fn debug_settings_read_the_output_dir() {
+    assert_eq!(debug_settings_from(None), DebugSettings::default());
+    assert_eq!(
+        debug_settings_from(Some(&serde_json::json!({ "debug_output_dir": "  " }))),
+        DebugSettings::default()
+    );
+    assert_eq!(
+        debug_settings_from(Some(
+            &serde_json::json!({ "debug_output_dir": " build/debug " })
+        )),
+        DebugSettings {
+            output_dir: Some("build/debug".to_string()),
+            downgrade_optimization: false,
+            wrapper_commands: Vec::new(),
+            host_program: None,
+            host_args: Vec::new(),
+            attach_program: None,
+            attach_wait_for: false,
+            break_on_panic: true,
+            test_track_memory: false,
+        }
+    );
+    assert!(
+        debug_settings_from(Some(
+            &serde_json::json!({ "debug_test_track_memory": true })
+        ))
+        .test_track_memory
+    );
+    assert!(
+        !debug_settings_from(Some(&serde_json::json!({ "debug_break_on_panic": false })))
+            .break_on_panic
+    );
+    let settings = debug_settings_from(Some(&serde_json::json!({
+        "debug_host_program": "bin/host",
+        "debug_host_args": ["--hot-reload", "game.so"],
+    })));
+    assert_eq!(settings.host_program.as_deref(), Some("bin/host"));
+    assert_eq!(settings.host_args, vec!["--hot-reload", "game.so"]);
+    assert_eq!(
+        debug_settings_from(Some(
+            &serde_json::json!({ "debug_wrapper_commands": ["./build.sh", " ", 3, "just"] })
+        ))
+        .wrapper_commands,
+        vec!["./build.sh".to_string(), "just".to_string()]
+    );
+    assert!(
+        debug_settings_from(Some(
+            &serde_json::json!({ "debug_downgrade_optimization": true })
+        ))
+        .downgrade_optimization
+    );
+
+    let mut settings = serde_json::json!({ "debug_output_dir": "out", "checker_args": "-vet" });
+    strip_extension_settings(&mut settings);
+    assert_eq!(settings, serde_json::json!({ "checker_args": "-vet" }));
+}
+
+#[test]
+fn absolute_paths_are_recognized_on_every_platform() {
+    assert!(is_absolute_path("/tmp/out"));
+    assert!(is_absolute_path("C:\\out"));
+    assert!(is_absolute_path("d:/out"));
+    assert!(is_absolute_path("\\\\server\\share"));
+    assert!(!is_absolute_path("out"));
+    assert!(!is_absolute_path(".zed/odin-debug"));
+    assert!(!is_absolute_path("C:out"));
+    assert!(!is_absolute_path(""));
+}
+
+#[test]
+fn debug_output_dir_defaults_to_a_per_project_dir_in_the_work_dir() {
+    let dir = debug_output_dir(None, "/ext/work/", "/home/me/game", "/");
+    assert!(dir.starts_with("/ext/work/odin-debug/game-"), "{dir}");
+    assert_ne!(
+        debug_output_dir(None, "/ext/work", "/home/me/game", "/"),
+        debug_output_dir(None, "/ext/work", "/home/you/game", "/")
+    );
+
+    assert_eq!(
+        debug_output_dir(Some("/tmp/odin/"), "/ext/work", "/home/me/game", "/"),
+        "/tmp/odin"
+    );
+    assert_eq!(
+        debug_output_dir(Some(".zed/odin-debug/"), "/ext/work", "/home/me/game", "/"),
+        "/home/me/game/.zed/odin-debug"
+    );
+    assert_eq!(
+        debug_output_dir(Some("bin"), "C:\\ext", "C:\\game", "\\"),
+        "C:\\game\\bin"
+    );
+    assert_eq!(debug_output_dir(Some("/"), "/ext", "/game", "/"), "/");
+}
+
+#[test]
+fn debug_output_dirs_never_fall_back_to_the_filesystem_root() {
+    assert_eq!(
+        debug_output_dir(None, "", "/home/me/game", "/"),
+        "/home/me/game/odin-debug"
+    );
+    assert_eq!(
+        debug_output_dir(None, "", "C:\\game\\", "\\"),
+        "C:\\game\\odin-debug"
+    );
+    assert_eq!(debug_output_dir(None, "", "", "/"), "odin-debug");
+    assert_eq!(debug_output_dir(Some("bin"), "/ext", "", "/"), "bin");
+}
+
+#[test]
+fn debug_project_keys_are_stable_and_readable() {
+    assert_eq!(
+        debug_project_key("/home/me/my game"),
+        debug_project_key("/home/me/my game")
+    );
+    assert!(debug_project_key("/home/me/my game").starts_with("my_game-"));
+    assert!(debug_project_key("C:\\code\\game").starts_with("game-"));
+    assert_eq!(debug_project_key("/").len(), 16);
+}
+
+#[test]
+fn stale_debug_outputs_are_old_builds_other_than_the_live_one() {
+    let now = 100 * DEBUG_OUTPUT_MAX_AGE_SECS;
+    let old = now - DEBUG_OUTPUT_MAX_AGE_SECS - 1;
+    let fresh = now - 60;
+    let entries: Vec<(String, u64)> = [
+        ("debug_build-main_odin", fresh),
+        ("debug_build-old_test", old),
+        ("debug_build-app.exe", old),
+        ("debug_build-app.pdb", old),
+        ("debug_build-gone.pdb", old),
+        ("debug_build-other_task", fresh),
+        ("notes.txt", old),
+    ]
+    .iter()
+    .map(|(name, modified)| (name.to_string(), *modified))
+    .collect();
+
+    // Builds of tasks that exist but were not debugged lately stay until
+    // they age out, whatever the extension has seen since it started.
+    assert_eq!(
+        stale_debug_outputs(&entries, "debug_build-app.exe", now),
+        vec!["debug_build-old_test", "debug_build-gone.pdb"]
+    );
+    assert_eq!(
+        stale_debug_outputs(&entries, "debug_build-main_odin", now),
+        vec![
+            "debug_build-old_test",
+            "debug_build-app.exe",
+            "debug_build-app.pdb",
+            "debug_build-gone.pdb",
+        ]
+    );
+    assert!(stale_debug_outputs(&entries, "debug_build-main_odin", fresh).is_empty());
+}
+
+#[test]
+fn debug_build_args_drop_conflicting_flags_and_keep_the_rest() {
+    let task = args(&[
+        "build",
+        ".",
+        "-out:bin/game",
+        "-o:speed",
+        "-build-mode:dll",
+        "-define:FOO=1",
+        "-collection:libs=libs",
+        "-target:linux_amd64",
+        "-vet",
+        "-vet-style",
+    ]);
+
+    assert_eq!(
+        sanitize_debug_build_args(&task, false),
+        args(&[
+            "build",
+            ".",
+            "-o:speed",
+            "-define:FOO=1",
+            "-collection:libs=libs",
+            "-target:linux_amd64",
+            "-vet",
+            "-vet-style",
+        ])
+    );
+    assert_eq!(
+        sanitize_debug_build_args(&task, true),
+        args(&[
+            "build",
+            ".",
+            "-o:none",
+            "-define:FOO=1",
+            "-collection:libs=libs",
+            "-target:linux_amd64",
+            "-vet",
+            "-vet-style",
+        ])
+    );
+    assert_eq!(
+        sanitize_debug_build_args(&args(&["build", "main.odin", "-file"]), true),
+        args(&["build", "main.odin", "-file"])
+    );
+}
+
+#[test]
+fn odin_commands_are_matched_by_base_name() {
+    assert_eq!(command_base_name("odin"), "odin");
+    assert_eq!(command_base_name("/opt/odin/odin"), "odin");
+    assert_eq!(command_base_name("$ODIN_ROOT/odin"), "odin");
+    assert_eq!(command_base_name("C:\\odin\\Odin.EXE"), "Odin");
+    assert_eq!(command_base_name("./build.sh"), "build.sh");
+
+    assert!(is_odin_command("odin", &[]));
+    assert!(is_odin_command("/opt/odin/odin", &[]));
+    assert!(is_odin_command("odin.exe", &[]));
+    assert!(is_odin_command("ODIN.EXE", &[]));
+    assert!(is_odin_command("C:\\odin\\Odin.exe", &[]));
+    assert!(is_odin_command("$ODIN_ROOT/odin", &[]));
+    assert!(!is_odin_command("odinfmt", &[]));
+    assert!(!is_odin_command("./build.sh", &[]));
+
+    let wrappers = vec!["build.sh".to_string(), "scripts/odinw".to_string()];
+    assert!(is_odin_command("./build.sh", &wrappers));
+    assert!(is_odin_command("odinw", &wrappers));
+    assert!(is_odin_command("ODINW.EXE", &wrappers));
+    assert!(!is_odin_command("./make.sh", &wrappers));
+}
+
+#[test]
+fn library_build_modes_are_detected_from_the_last_flag() {
+    assert_eq!(
+        library_build_mode(&args(&["build", ".", "-build-mode:dll"])),
+        Some("dll")
+    );
+    assert_eq!(
+        library_build_mode(&args(&["build", ".", "-build-mode:shared"])),
+        Some("shared")
+    );
+    assert_eq!(
+        library_build_mode(&args(&["build", ".", "-build-mode:dll", "-build-mode:exe"])),
+        None
+    );
+    assert_eq!(
+        library_build_mode(&args(&["build", ".", "-build-mode:obj"])),
+        None
+    );
+    assert_eq!(library_build_mode(&args(&["build", "."])), None);
+}
+
+#[test]
+fn only_executable_test_and_library_builds_are_debuggable() {
+    assert!(is_debuggable_build_mode(&args(&["run", "."])));
+    for mode in ["exe", "test", "dll", "shared", "dynamic"] {
+        let flag = format!("-build-mode:{mode}");
+        assert!(
+            is_debuggable_build_mode(&args(&["build", ".", &flag])),
+            "{mode}"
+        );
+    }
+    for mode in [
+        "obj", "object", "lib", "static", "asm", "assembly", "llvm-ir", "llvm",
+    ] {
+        let flag = format!("-build-mode:{mode}");
+        assert!(
+            !is_debuggable_build_mode(&args(&["build", ".", &flag])),
+            "{mode}"
+        );
+    }
+    assert!(is_debuggable_build_mode(&args(&[
+        "build",
+        ".",
+        "-build-mode:obj",
+        "-build-mode:exe"
+    ])));
+}
+
+#[test]
+fn task_paths_resolve_against_the_cwd_unless_absolute() {
+    assert_eq!(
+        resolve_task_path("bin/host", "/game", "/"),
+        "/game/bin/host"
+    );
+    assert_eq!(resolve_task_path("/opt/host", "/game", "/"), "/opt/host");
+    assert_eq!(
+        resolve_task_path("host.exe", "C:\\game", "\\"),
+        "C:\\game\\host.exe"
+    );
+}
+
+#[test]
+fn preloaded_libraries_are_quoted_for_lldb() {
+    assert_eq!(
+        preload_library_command("/tmp/debug_build-game.so"),
+        "target modules add \"/tmp/debug_build-game.so\""
+    );
+    assert_eq!(
+        preload_library_command("/tmp/a \"b\".so"),
+        "target modules add \"/tmp/a \\\"b\\\".so\""
+    );
+}
+
+#[test]
+fn attach_targets_prefer_pid_then_setting_then_last_build() {
+    let configured = DebugSettings {
+        attach_program: Some("game".to_string()),
+        ..DebugSettings::default()
+    };
+
+    assert_eq!(
+        attach_target(Some(42), &configured, Some("/out/debug_build-app")),
+        Some(AttachTarget::Pid(42))
+    );
+    assert_eq!(
+        attach_target(None, &configured, Some("/out/debug_build-app")),
+        Some(AttachTarget::Program {
+            program: "game".to_string(),
+            wait_for: false,
+        })
+    );
+    assert_eq!(
+        attach_target(
+            None,
+            &DebugSettings {
+                attach_wait_for: true,
+                ..configured
+            },
+            None
+        ),
+        Some(AttachTarget::Program {
+            program: "game".to_string(),
+            wait_for: true,
+        })
+    );
+    assert_eq!(
+        attach_target(
+            None,
+            &DebugSettings::default(),
+            Some("/out/debug_build-app")
+        ),
+        Some(AttachTarget::Program {
+            program: "/out/debug_build-app".to_string(),
+            wait_for: true,
+        })
+    );
+    assert_eq!(attach_target(None, &DebugSettings::default(), None), None);
+}
+
+#[test]
+fn attach_configs_use_pid_or_program_and_wait_for() {
+    assert_eq!(
+        serde_json::Value::Object(attach_config(&AttachTarget::Pid(7))),
+        serde_json::json!({ "request": "attach", "pid": 7 })
+    );
+    assert_eq!(
+        serde_json::Value::Object(attach_config(&AttachTarget::Program {
+            program: "game".to_string(),
+            wait_for: false,
+        })),
+        serde_json::json!({ "request": "attach", "program": "game" })
+    );
+    assert_eq!(
+        serde_json::Value::Object(attach_config(&AttachTarget::Program {
+            program: "/out/app".to_string(),
+            wait_for: true,
+        })),
+        serde_json::json!({ "request": "attach", "program": "/out/app", "waitFor": true })
+    );
+}
+
+#[test]
+fn pre_run_commands_are_prepended_without_losing_user_commands() {
+    let formatter = "script import odin".to_string();
+
+    let mut config = serde_json::Map::new();
+    inject_pre_run_commands(&mut config, vec![formatter.clone()]);
+    assert_eq!(
+        serde_json::Value::Object(config.clone()),
+        serde_json::json!({ "preRunCommands": ["script import odin"] })
+    );
+
+    // Converting the same config again must not duplicate the formatter.
+    inject_pre_run_commands(&mut config, vec![formatter.clone()]);
+    assert_eq!(
+        config["preRunCommands"],
+        serde_json::json!(["script import odin"])
+    );
+
+    let serde_json::Value::Object(mut config) = serde_json::json!({
+        "request": "launch",
+        "preRunCommands": ["settings set target.x86-disassembly-flavor intel"],
+    }) else {
+        unreachable!()
+    };
+    inject_pre_run_commands(&mut config, vec![formatter.clone()]);
+    assert_eq!(
+        config["preRunCommands"],
+        serde_json::json!([
+            "script import odin",
+            "settings set target.x86-disassembly-flavor intel",
+        ])
+    );
+
+    let serde_json::Value::Object(mut config) =
+        serde_json::json!({ "preRunCommands": "breakpoint set -n main" })
+    else {
+        unreachable!()
+    };
+    inject_pre_run_commands(&mut config, vec![formatter]);
+    assert_eq!(
+        config["preRunCommands"],
+        serde_json::json!(["script import odin", "breakpoint set -n main"])
+    );
+}
+
+#[test]
+fn panic_breakpoint_covers_every_runtime_failure_handler() {
+    let command = panic_breakpoint_command();
+
+    assert!(command.starts_with("breakpoint set -N odin-panic "));
+    for symbol in PANIC_BREAKPOINT_SYMBOLS {
+        assert!(command.contains(&format!(" -n {symbol}")), "{symbol}");
+    }
+    assert!(!command.contains("bounds_check_error"));
+    assert!(!command.contains("runtime.panic"));
+    assert_eq!(
+        command,
+        "breakpoint set -N odin-panic \
+         -n runtime.default_assertion_failure_proc \
+         -n runtime.default_assertion_contextless_failure_proc \
+         -n testing.test_assertion_failure_proc \
+         -n runtime.bounds_trap \
+         -n runtime.type_assertion_trap"
+    );
+    assert!(debug_settings_from(None).break_on_panic);
+}
+
+#[test]
+fn test_debug_builds_pin_the_runner_defines_and_keep_test_names() {
+    let mut build_args = args(&[
+        "build",
+        "/game/src",
+        "-define:ODIN_TEST_NAMES=src.test_parse",
+        "-define:ODIN_TEST_CLIPBOARD=true",
+        "-define:ODIN_TEST_THREADS=8",
+        "-define:GAME_DEBUG=true",
+    ]);
+    apply_test_debug_defines(&mut build_args, false);
+    assert_eq!(
+        build_args,
+        args(&[
+            "build",
+            "/game/src",
+            "-define:ODIN_TEST_NAMES=src.test_parse",
+            "-define:GAME_DEBUG=true",
+            "-define:ODIN_TEST_THREADS=1",
+            "-define:ODIN_TEST_CLIPBOARD=false",
+            "-define:ODIN_TEST_FANCY=false",
+            "-define:ODIN_TEST_TRACK_MEMORY=false",
+        ])
+    );
+
+    let mut build_args = args(&["build", "."]);
+    apply_test_debug_defines(&mut build_args, true);
+    assert!(build_args.contains(&"-define:ODIN_TEST_TRACK_MEMORY=true".to_string()));
+    assert!(!build_args.contains(&"-define:ODIN_TEST_TRACK_MEMORY=false".to_string()));
+
+    // A task that asks for memory tracking itself keeps it, whatever the
+    // setting says.
+    let mut build_args = args(&["build", ".", "-define:ODIN_TEST_TRACK_MEMORY=true"]);
+    apply_test_debug_defines(&mut build_args, false);
+    assert_eq!(
+        build_args,
+        args(&[
+            "build",
+            ".",
+            "-define:ODIN_TEST_TRACK_MEMORY=true",
+            "-define:ODIN_TEST_THREADS=1",
+            "-define:ODIN_TEST_CLIPBOARD=false",
+            "-define:ODIN_TEST_FANCY=false",
+        ])
+    );
+}
+
+#[test]
+fn sanitizers_are_read_from_the_task_flags() {
+    assert_eq!(
+        requested_sanitizers(&args(&[
+            "run",
+            ".",
+            "-sanitize:address",
+            "-sanitize:bogus",
+            "-sanitize:address",
+            "-sanitize:thread",
+        ])),
+        vec!["address", "thread"]
+    );
+    assert!(requested_sanitizers(&args(&["run", "."])).is_empty());
+}
+
+#[test]
+fn sanitized_scenarios_get_distinct_labels_and_outputs() {
+    assert_eq!(sanitizer_label("run: 'main.odin'", &[]), "run: 'main.odin'");
+    assert_eq!(
+        sanitizer_label("run: 'main.odin'", &["address"]),
+        "run: 'main.odin' (address sanitizer)"
+    );
+    assert_eq!(
+        sanitizer_label("test: 'src'", &["address", "thread"]),
+        "test: 'src' (address + thread sanitizer)"
+    );
+    assert_eq!(
+        sanitizer_label("run: package 'src' (memory sanitizer)", &["memory"]),
+        "run: package 'src' (memory sanitizer)"
+    );
+
+    assert_eq!(
+        sanitizer_label("test: 'test_sanitizer_setup'", &["address"]),
+        "test: 'test_sanitizer_setup' (address sanitizer)"
+    );
+    assert_eq!(
+        sanitizer_label("run: 'main.odin' (thread sanitizer)", &["address"]),
+        "run: 'main.odin' (thread sanitizer) (address sanitizer)"
+    );
+    assert_ne!(
+        debug_output_name(
+            &sanitizer_label("test: 'test_sanitizer_setup'", &["address"]),
+            ""
+        ),
+        debug_output_name("test: 'test_sanitizer_setup'", "")
+    );
+    assert_ne!(
+        debug_output_name(&sanitizer_label("run: 'main.odin'", &["address"]), ""),
+        debug_output_name("run: 'main.odin'", "")
+    );
+    assert_ne!(
+        debug_output_name(&sanitizer_label("run: 'main.odin'", &["address"]), ""),
+        debug_output_name(&sanitizer_label("run: 'main.odin'", &["thread"]), "")
+    );
+}
+
+#[test]
+fn sanitizer_env_keeps_options_the_task_sets() {
+    let mut env = vec![("TSAN_OPTIONS".to_string(), "verbosity=1".to_string())];
+    add_sanitizer_env(&mut env, &["address", "thread"]);
+    assert_eq!(
+        env,
+        vec![
+            ("TSAN_OPTIONS".to_string(), "verbosity=1".to_string()),
+            (
+                "ASAN_OPTIONS".to_string(),
+                "abort_on_error=1:detect_leaks=0".to_string()
+            ),
+        ]
+    );
+
+    let mut env = Vec::new();
+    add_sanitizer_env(&mut env, &[]);
+    assert!(env.is_empty());
+}